@@ -0,0 +1,85 @@
+// Compares a plain single-threaded walk with the parallel Smart Sync walker on
+// a synthetic monorepo of 100k files (100 packages x 10 dependencies x 100 files).
+//
+//     cargo bench --bench walk
+//
+// The tree is built once under the system temp dir and reused between runs.
+
+use app_lib::walk::{walk, WalkOptions};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+const PACKAGES: usize = 100;
+const DEPENDENCIES: usize = 10;
+const FILES: usize = 100;
+const RUNS: usize = 5;
+
+fn build_tree(root: &Path) -> std::io::Result<()> {
+    let marker = root.join(".complete");
+    if marker.exists() {
+        return Ok(());
+    }
+    for p in 0..PACKAGES {
+        for d in 0..DEPENDENCIES {
+            let dir = root.join(format!("packages/pkg{}/node_modules/dep{}/lib", p, d));
+            std::fs::create_dir_all(&dir)?;
+            for f in 0..FILES {
+                std::fs::write(dir.join(format!("file{}.js", f)), "module.exports = 1;\n")?;
+            }
+        }
+    }
+    std::fs::write(marker, "")
+}
+
+// What Smart Sync did before: recursive read_dir on one thread.
+fn sequential_count(path: &Path) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| match e.file_type() {
+                    Ok(t) if t.is_dir() => sequential_count(&e.path()),
+                    _ => 1,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn parallel_count(path: &Path, options: &WalkOptions) -> u64 {
+    let files = AtomicU64::new(0);
+    walk(path, options, |entry| {
+        if !entry.file_type.is_dir() {
+            files.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    })
+    .expect("walk failed");
+    files.into_inner()
+}
+
+// Best of RUNS, so the page cache is warm for every variant.
+fn bench(label: &str, baseline: Option<Duration>, f: impl Fn() -> u64) -> Duration {
+    let mut best = Duration::MAX;
+    let mut files = 0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        files = f();
+        best = best.min(start.elapsed());
+    }
+    let speedup = baseline.map(|b| format!("{:.2}x", b.as_secs_f64() / best.as_secs_f64())).unwrap_or_default();
+    println!("{:<32} {:>8} files {:>10.1?} {:>8}", label, files, best, speedup);
+    best
+}
+
+fn main() {
+    let root = std::env::temp_dir().join("gwm-walk-bench");
+    build_tree(&root).expect("failed to build the synthetic tree");
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    println!("{} files under {}, {} threads\n", PACKAGES * DEPENDENCIES * FILES, root.display(), threads);
+
+    let baseline = bench("sequential read_dir", None, || sequential_count(&root));
+    bench("walker, 1 thread", Some(baseline), || parallel_count(&root, &WalkOptions { threads: 1, ..Default::default() }));
+    bench(&format!("walker, all cores ({})", threads), Some(baseline), || parallel_count(&root, &WalkOptions::default()));
+}
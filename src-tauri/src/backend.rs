@@ -0,0 +1,312 @@
+use serde::Serialize;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::error::AppError;
+use crate::git::Git;
+use crate::status::{AheadBehind, CommitInfo, WorktreeStatus};
+use crate::Worktree;
+
+pub use crate::libgit2::Libgit2Backend;
+
+// Environment variable choosing the backend: "cli" (default) or "libgit2"
+pub const BACKEND_ENV: &str = "GWM_GIT_BACKEND";
+
+// A local or remote-tracking branch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Branch {
+    // Short name: "main", "origin/main"
+    pub name: String,
+    pub is_remote: bool,
+    pub target: String,
+    // Short name of the configured upstream, even when its ref is gone
+    pub upstream: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RefInfo {
+    // Full name: "refs/heads/main", "refs/tags/v1"
+    pub name: String,
+    // Object the ref points at, after following symbolic refs (not peeled)
+    pub target: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct DiffStats {
+    pub files_changed: u32,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+// Read-only queries the UI polls for every worktree. Implemented by running the
+// git CLI and in-process with libgit2; both must give the same answers (see
+// tests/git_backend.rs). Calls block, so async code runs them on the blocking pool.
+pub trait GitBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Every worktree of the repository `repo` belongs to: the main one first,
+    // then the linked ones ordered by path.
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<Worktree>, AppError>;
+
+    // File counts, upstream and `base` divergence and last commit of a worktree.
+    fn status(&self, worktree: &Path, base: Option<&str>) -> Result<WorktreeStatus, AppError>;
+
+    // Local branches, then remote-tracking ones, each ordered by name. Remote HEADs are left out.
+    fn branches(&self, repo: &Path) -> Result<Vec<Branch>, AppError>;
+
+    // Every ref under refs/, ordered by name.
+    fn refs(&self, repo: &Path) -> Result<Vec<RefInfo>, AppError>;
+
+    // Tracked changes in the working tree (index included) against `against`,
+    // HEAD by default, like `git diff <against>`. Renames count as a deletion and an addition.
+    fn diff_stats(&self, worktree: &Path, against: Option<&str>) -> Result<DiffStats, AppError>;
+}
+
+// The backend chosen with GWM_GIT_BACKEND, read once at first use.
+pub fn current() -> &'static dyn GitBackend {
+    static BACKEND: OnceLock<Box<dyn GitBackend>> = OnceLock::new();
+    BACKEND
+        .get_or_init(|| match std::env::var(BACKEND_ENV).as_deref() {
+            Ok("libgit2") | Ok("git2") => Box::new(Libgit2Backend),
+            Ok("cli") | Err(_) => Box::new(CliBackend),
+            Ok(other) => {
+                println!("Unknown {} '{}', using the git CLI", BACKEND_ENV, other);
+                Box::new(CliBackend)
+            }
+        })
+        .as_ref()
+}
+
+// Runs `git` for every query (through git::Git, so it shares its limits).
+pub struct CliBackend;
+
+impl GitBackend for CliBackend {
+    fn name(&self) -> &'static str {
+        "cli"
+    }
+
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<Worktree>, AppError> {
+        let output = Git::new(repo).args(["worktree", "list", "--porcelain", "-z"]).run_blocking()?;
+        let mut worktrees = parse_worktrees(&output);
+        if worktrees.len() > 1 {
+            worktrees[1..].sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        Ok(worktrees)
+    }
+
+    fn status(&self, worktree: &Path, base: Option<&str>) -> Result<WorktreeStatus, AppError> {
+        let mut status = WorktreeStatus {
+            path: worktree.to_string_lossy().to_string(),
+            base: base.map(|b| b.to_string()),
+            ..Default::default()
+        };
+        let output = Git::new(worktree)
+            .args(["status", "--porcelain=v2", "--branch", "-z"])
+            // Refreshing the index would wake the watcher (see watcher.rs) over and over
+            .env("GIT_OPTIONAL_LOCKS", "0")
+            .run_blocking()?;
+        parse_status(&output, &mut status);
+
+        if let Some(base) = base {
+            let range = format!("{}...HEAD", base);
+            status.base_ahead_behind = Git::new(worktree)
+                .args(["rev-list", "--left-right", "--count", &range])
+                .run_blocking()
+                .ok()
+                .and_then(|o| parse_left_right(&o));
+        }
+
+        status.last_commit = Git::new(worktree)
+            .args(["log", "-1", "--format=%H%x00%s%x00%an%x00%ct"])
+            .run_blocking()
+            .ok()
+            .and_then(|o| parse_commit(&o));
+        Ok(status)
+    }
+
+    fn branches(&self, repo: &Path) -> Result<Vec<Branch>, AppError> {
+        let output = Git::new(repo)
+            .args(["for-each-ref", "--format=%(refname)%00%(objectname)%00%(upstream)%00%(symref)", "refs/heads", "refs/remotes"])
+            .run_blocking()?;
+        let mut branches: Vec<Branch> = output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\0');
+                let (name, target, upstream, symref) = (fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+                if !symref.is_empty() {
+                    return None;
+                }
+                Some(Branch {
+                    name: short_branch_name(name).to_string(),
+                    is_remote: name.starts_with("refs/remotes/"),
+                    target: target.to_string(),
+                    upstream: Some(short_branch_name(upstream).to_string()).filter(|u| !u.is_empty()),
+                })
+            })
+            .collect();
+        branches.sort_by(|a, b| (a.is_remote, &a.name).cmp(&(b.is_remote, &b.name)));
+        Ok(branches)
+    }
+
+    fn refs(&self, repo: &Path) -> Result<Vec<RefInfo>, AppError> {
+        let output = Git::new(repo).args(["for-each-ref", "--format=%(refname)%00%(objectname)"]).run_blocking()?;
+        let mut refs: Vec<RefInfo> = output
+            .lines()
+            .filter_map(|line| {
+                let (name, target) = line.split_once('\0')?;
+                Some(RefInfo { name: name.to_string(), target: target.to_string() })
+            })
+            .collect();
+        refs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(refs)
+    }
+
+    fn diff_stats(&self, worktree: &Path, against: Option<&str>) -> Result<DiffStats, AppError> {
+        let output = Git::new(worktree)
+            .args(["diff", "--shortstat", "--no-renames", "--no-ext-diff", "--no-textconv", against.unwrap_or("HEAD"), "--"])
+            .env("GIT_OPTIONAL_LOCKS", "0")
+            .run_blocking()?;
+        Ok(parse_shortstat(&output))
+    }
+}
+
+// "refs/heads/main" -> "main", "refs/remotes/origin/main" -> "origin/main"
+pub(crate) fn short_branch_name(name: &str) -> &str {
+    name.strip_prefix("refs/heads/").or_else(|| name.strip_prefix("refs/remotes/")).unwrap_or(name)
+}
+
+// Parses `git worktree list --porcelain -z` output.
+// Every attribute is NUL-terminated and an empty attribute ends the record,
+// so paths containing newlines or other odd characters survive intact.
+fn parse_worktrees(output: &str) -> Vec<Worktree> {
+    let mut worktrees = Vec::new();
+    let mut current_worktree = Worktree::default();
+    let mut has_data = false;
+
+    for field in output.split('\0') {
+        if field.is_empty() {
+            if has_data {
+                worktrees.push(std::mem::take(&mut current_worktree));
+                has_data = false;
+            }
+            continue;
+        }
+
+        // Attributes are "<label>" or "<label> <value>"
+        let (label, value) = match field.split_once(' ') {
+            Some((label, value)) => (label, Some(value)),
+            None => (field, None),
+        };
+
+        match label {
+            "worktree" => {
+                current_worktree.path = value.unwrap_or_default().to_string();
+                has_data = true;
+            }
+            "HEAD" => current_worktree.head_hash = value.unwrap_or_default().to_string(),
+            "branch" => {
+                let ref_name = value.unwrap_or_default();
+                let branch_name = ref_name.strip_prefix("refs/heads/").unwrap_or(ref_name);
+                current_worktree.branch = Some(branch_name.to_string());
+            }
+            "bare" => current_worktree.is_bare = true,
+            "detached" => current_worktree.is_detached = true,
+            "locked" => {
+                current_worktree.is_locked = true;
+                current_worktree.lock_reason = value.map(|v| v.to_string());
+            }
+            "prunable" => {
+                current_worktree.is_prunable = true;
+                current_worktree.prunable_reason = value.map(|v| v.to_string());
+            }
+            _ => {}
+        }
+    }
+    if has_data {
+        worktrees.push(current_worktree);
+    }
+    worktrees
+}
+
+// Parses `git status --porcelain=v2 --branch -z` into file counts and upstream info.
+fn parse_status(output: &str, status: &mut WorktreeStatus) {
+    let mut fields = output.split('\0');
+
+    while let Some(field) = fields.next() {
+        if let Some(header) = field.strip_prefix("# ") {
+            if let Some(upstream) = header.strip_prefix("branch.upstream ") {
+                status.upstream = Some(upstream.to_string());
+            } else if let Some(ab) = header.strip_prefix("branch.ab ") {
+                // "+<ahead> -<behind>"
+                let mut parts = ab.split(' ');
+                let ahead = parts.next().and_then(|a| a.trim_start_matches('+').parse().ok());
+                let behind = parts.next().and_then(|b| b.trim_start_matches('-').parse().ok());
+                if let (Some(ahead), Some(behind)) = (ahead, behind) {
+                    status.upstream_ahead_behind = Some(AheadBehind { ahead, behind });
+                }
+            }
+            continue;
+        }
+
+        match field.chars().next() {
+            Some('1') | Some('2') => {
+                let xy = field.get(2..4).unwrap_or("..");
+                let mut xy = xy.chars();
+                if xy.next().is_some_and(|x| x != '.') {
+                    status.staged += 1;
+                }
+                if xy.next().is_some_and(|y| y != '.') {
+                    status.unstaged += 1;
+                }
+                // Renames and copies carry the original path as an extra field
+                if field.starts_with('2') {
+                    fields.next();
+                }
+            }
+            Some('u') => status.conflicted += 1,
+            Some('?') => status.untracked += 1,
+            _ => {}
+        }
+    }
+}
+
+// Parses `git rev-list --left-right --count <base>...HEAD` ("<behind>\t<ahead>").
+fn parse_left_right(output: &str) -> Option<AheadBehind> {
+    let mut parts = output.split_whitespace();
+    let behind = parts.next()?.parse().ok()?;
+    let ahead = parts.next()?.parse().ok()?;
+    Some(AheadBehind { ahead, behind })
+}
+
+// Parses `git log -1 --format=%H%x00%s%x00%an%x00%ct`.
+fn parse_commit(output: &str) -> Option<CommitInfo> {
+    let mut parts = output.trim_end_matches('\n').splitn(4, '\0');
+    Some(CommitInfo {
+        hash: parts.next()?.to_string(),
+        subject: parts.next()?.to_string(),
+        author: parts.next()?.to_string(),
+        timestamp: parts.next()?.trim().parse().ok()?,
+    })
+}
+
+// Parses `git diff --shortstat` (" 3 files changed, 10 insertions(+), 2 deletions(-)").
+// Prints nothing when there are no changes; either count is left out when zero.
+fn parse_shortstat(output: &str) -> DiffStats {
+    let mut stats = DiffStats::default();
+    for part in output.trim().split(", ") {
+        let Some((count, what)) = part.split_once(' ') else {
+            continue;
+        };
+        let Ok(count) = count.parse() else {
+            continue;
+        };
+        if what.starts_with("file") {
+            stats.files_changed = count;
+        } else if what.starts_with("insertion") {
+            stats.insertions = count;
+        } else if what.starts_with("deletion") {
+            stats.deletions = count;
+        }
+    }
+    stats
+}
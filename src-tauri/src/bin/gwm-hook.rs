@@ -0,0 +1,143 @@
+// Claude Code hook installed by the app: `gwm-hook [--token <secret>] <Event>`.
+// Reads the hook input Claude writes to stdin and reports the session's state
+// to the running app through the address in its discovery file (see hook_server.rs).
+// Prints nothing on stdout, which Claude would read as a hook decision.
+
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+// Claude waits for hooks; never hold it up for long
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Request {
+    token: Option<String>,
+    event: String,
+}
+
+fn parse_args() -> Result<Request, String> {
+    let mut token = None;
+    let mut event = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--token" => token = Some(args.next().ok_or("--token needs a value")?),
+            _ if event.is_none() => event = Some(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+    let event = event.ok_or("usage: gwm-hook [--token <secret>] <Event>")?;
+    Ok(Request { token, event })
+}
+
+// Agent state shown on the worktree card for a hook event.
+fn status_for(event: &str) -> Option<&'static str> {
+    match event {
+        "PermissionRequest" => Some("waiting_auth"),
+        "PreToolUse" | "PostToolUse" => Some("running"),
+        "Stop" => Some("idle"),
+        _ => None,
+    }
+}
+
+// Same location as hook_server::claude_dir.
+fn discovery_path() -> Option<PathBuf> {
+    let home = if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" };
+    let home = std::env::var_os(home).filter(|h| !h.is_empty())?;
+    Some(PathBuf::from(home).join(".claude").join("hooks").join("git-worktree-manager.json"))
+}
+
+// Sends the request over the app's Unix socket when it has one, over TCP otherwise,
+// and returns the status code of the answer.
+fn post(discovery: &Value, token: Option<&str>, body: &str) -> std::io::Result<u16> {
+    let mut request = format!(
+        "POST /claude/status HTTP/1.0\r\nHost: localhost\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\n",
+        body.len()
+    );
+    if let Some(token) = token {
+        request.push_str(&format!("Authorization: Bearer {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+
+    let mut reply = String::new();
+    #[cfg(unix)]
+    if let Some(socket) = discovery["socket"].as_str() {
+        if let Ok(mut stream) = std::os::unix::net::UnixStream::connect(socket) {
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            stream.write_all(request.as_bytes())?;
+            stream.read_to_string(&mut reply)?;
+            return status_code(&reply);
+        }
+    }
+
+    let port = discovery["port"].as_u64().and_then(|p| u16::try_from(p).ok()).ok_or_else(|| invalid("no port in the discovery file"))?;
+    let mut stream = TcpStream::connect_timeout(&SocketAddr::from(([127, 0, 0, 1], port)), TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    stream.write_all(request.as_bytes())?;
+    stream.read_to_string(&mut reply)?;
+    status_code(&reply)
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+// "HTTP/1.1 204 No Content" -> 204
+fn status_code(reply: &str) -> std::io::Result<u16> {
+    reply.split_whitespace().nth(1).and_then(|code| code.parse().ok()).ok_or_else(|| invalid("malformed reply from the app"))
+}
+
+fn main() -> ExitCode {
+    let request = match parse_args() {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("gwm-hook: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    // Installed for the events below only; anything else is not ours to report
+    let Some(status) = status_for(&request.event) else {
+        return ExitCode::SUCCESS;
+    };
+
+    let mut input = String::new();
+    let _ = std::io::stdin().read_to_string(&mut input);
+    let input: Value = serde_json::from_str(&input).unwrap_or(Value::Null);
+    // Claude passes the session's directory as "cwd"; hooks also start there
+    let path = match input["cwd"].as_str() {
+        Some(cwd) => cwd.to_string(),
+        None => std::env::current_dir().map(|d| d.to_string_lossy().to_string()).unwrap_or_default(),
+    };
+    let body = serde_json::json!({
+        "path": path,
+        "status": status,
+        "message": input["message"].as_str(),
+    })
+    .to_string();
+
+    // No discovery file or nobody listening: the app is not running, which is fine
+    let Some(discovery) = discovery_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+    else {
+        return ExitCode::SUCCESS;
+    };
+    match post(&discovery, request.token.as_deref(), &body) {
+        Ok(code) if (200..300).contains(&code) => ExitCode::SUCCESS,
+        Ok(code) => {
+            eprintln!("gwm-hook: git-worktree-manager answered {}; reinstall the hooks from the app", code);
+            ExitCode::FAILURE
+        }
+        Err(e) if matches!(e.kind(), std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::NotFound) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("gwm-hook: could not reach git-worktree-manager: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::Stdio;
+use tauri::{AppHandle, Emitter};
+
+use crate::smart_sync::{self, PlannedItem, SmartSyncConfig, SyncStrategy};
+use crate::{create_command, run_git};
+
+// Event streaming installer output and the final outcome to the UI.
+const BOOTSTRAP_EVENT: &str = "dependency-bootstrap";
+
+// Installer output lines kept for the error message when it fails
+const ERROR_TAIL_LINES: usize = 20;
+
+// What to do with dependency folders whose lockfile differs from the source's.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MismatchAction {
+    // Run the install command in the new worktree (skipping folders no command rebuilds, copying when it fails)
+    #[default]
+    Install,
+    // Give the worktree its own copy of the source's folder
+    Copy,
+    // Link anyway, as if the lockfiles matched
+    Link,
+}
+
+// Lockfile, the folder it populates and the command that rebuilds that folder from the lockfile alone.
+const DEPENDENCY_LOCKFILES: &[(&str, &str, Option<&str>)] = &[
+    ("package-lock.json", "node_modules", Some("npm ci")),
+    ("npm-shrinkwrap.json", "node_modules", Some("npm ci")),
+    ("pnpm-lock.yaml", "node_modules", Some("pnpm install --frozen-lockfile --prefer-offline")),
+    ("yarn.lock", "node_modules", Some("yarn install --frozen-lockfile")),
+    ("bun.lockb", "node_modules", Some("bun install --frozen-lockfile")),
+    ("Cargo.lock", "target", None),
+];
+
+// Git blob ids of the lockfiles at the root of `dir`, as (name, id) pairs.
+pub fn lockfile_hashes(dir: &str) -> Vec<(String, String)> {
+    let present: Vec<&str> = DEPENDENCY_LOCKFILES
+        .iter()
+        .map(|(name, _, _)| *name)
+        .filter(|f| Path::new(dir).join(f).is_file())
+        .collect();
+    if present.is_empty() {
+        return Vec::new();
+    }
+    let mut args = vec!["hash-object", "--"];
+    args.extend(&present);
+    let Ok(output) = run_git(dir, &args) else {
+        return Vec::new();
+    };
+    present.iter().map(|f| f.to_string()).zip(output.lines().map(|l| l.trim().to_string())).collect()
+}
+
+// The same for the root of commit `rev`, for a worktree that is not checked out yet.
+pub fn lockfile_hashes_at(repo: &str, rev: &str) -> Vec<(String, String)> {
+    let mut args = vec!["ls-tree", rev, "--"];
+    args.extend(DEPENDENCY_LOCKFILES.iter().map(|(name, _, _)| *name));
+    let Ok(output) = run_git(repo, &args) else {
+        return Vec::new();
+    };
+    // "<mode> blob <id>\t<name>"
+    output
+        .lines()
+        .filter_map(|line| {
+            let (info, name) = line.split_once('\t')?;
+            let id = info.split_whitespace().nth(2)?;
+            Some((name.to_string(), id.to_string()))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrapStatus {
+    // Install command still running in the background
+    Running,
+    Installed,
+    // Dependency folders copied from the source (by choice, or because the install failed)
+    Copied,
+    // Dependency folders linked despite the mismatch
+    Linked,
+    // Left for the worktree's own build, as no command rebuilds them
+    Skipped,
+}
+
+// How dependency folders were handled when lockfiles diverged. Part of the Smart Sync report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootstrapReport {
+    // Lockfiles in the worktree that differ from (or are missing in) the source
+    pub lockfiles: Vec<String>,
+    pub action: MismatchAction,
+    pub command: Option<String>,
+    pub status: BootstrapStatus,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BootstrapEvent {
+    pub worktree_path: String,
+    // One line of installer output; None on the final event
+    pub line: Option<String>,
+    pub status: BootstrapStatus,
+}
+
+// Lockfiles that differ between source and worktree, and what to do about the folders they populate.
+pub struct Divergence {
+    lockfiles: Vec<String>,
+    // Affected folders, each with the command that rebuilds it from its own lockfile
+    dirs: Vec<(&'static str, Option<String>)>,
+    action: MismatchAction,
+}
+
+// None when every lockfile of the worktree (`target`, from `lockfile_hashes`) matches the source.
+pub fn detect(source_path: &str, target: Vec<(String, String)>, config: &SmartSyncConfig) -> Option<Divergence> {
+    let source = lockfile_hashes(source_path);
+    let lockfiles: Vec<String> = target
+        .into_iter()
+        .filter(|lock| !source.contains(lock))
+        .map(|(name, _)| name)
+        .collect();
+    if lockfiles.is_empty() {
+        return None;
+    }
+
+    // A custom command stands in for the built-in installers, not for folders none of them rebuild
+    let custom = config.install_command.clone().filter(|c| !c.trim().is_empty());
+    let mut dirs: Vec<(&'static str, Option<String>)> = Vec::new();
+    for (name, dir, command) in DEPENDENCY_LOCKFILES {
+        if lockfiles.iter().any(|l| l == name) && !dirs.iter().any(|(d, _)| d == dir) {
+            dirs.push((*dir, command.map(|c| custom.clone().unwrap_or_else(|| c.to_string()))));
+        }
+    }
+
+    Some(Divergence { lockfiles, dirs, action: config.lockfile_mismatch })
+}
+
+// The bootstrap report and the dependency folders left for the install command.
+pub type Bootstrap = (BootstrapReport, Vec<PlannedItem>);
+
+impl Divergence {
+    // Rewrites the plan for the affected dependency folders at the worktree
+    // root, next to the lockfiles compared: copied for `Copy`, left to the
+    // install command or skipped (when none rebuilds them) for `Install`, and
+    // linked for `Link`. Returns the bootstrap report and the folders the
+    // install command should produce, or None when the plan has no such folder.
+    pub fn apply(&self, worktree_path: &str, plan: &mut [PlannedItem]) -> Option<Bootstrap> {
+        let root = Path::new(worktree_path);
+        let mut affected = false;
+        let mut deferred = Vec::new();
+        let mut commands: Vec<String> = Vec::new();
+        for item in plan.iter_mut().filter(|i| i.is_dir && !i.exists && i.strategy != SyncStrategy::Skip) {
+            // Nested folders (e.g. a monorepo package's node_modules) have lockfiles of their own
+            let Some((_, command)) = self.dirs.iter().find(|(dir, _)| Path::new(&item.destination) == root.join(dir)) else {
+                continue;
+            };
+            affected = true;
+            match (self.action, command) {
+                (MismatchAction::Install, Some(command)) => {
+                    deferred.push(item.clone());
+                    if !commands.contains(command) {
+                        commands.push(command.clone());
+                    }
+                    item.strategy = SyncStrategy::Skip;
+                }
+                // Nothing rebuilds it, and sharing it (e.g. `target`) across lockfiles breaks both builds
+                (MismatchAction::Install, None) => item.strategy = SyncStrategy::Skip,
+                (MismatchAction::Copy, _) => item.strategy = SyncStrategy::Copy,
+                (MismatchAction::Link, _) => {}
+            }
+        }
+        if !affected {
+            return None;
+        }
+
+        let status = match self.action {
+            _ if !deferred.is_empty() => BootstrapStatus::Running,
+            MismatchAction::Install => BootstrapStatus::Skipped,
+            MismatchAction::Copy => BootstrapStatus::Copied,
+            MismatchAction::Link => BootstrapStatus::Linked,
+        };
+        let report = BootstrapReport {
+            lockfiles: self.lockfiles.clone(),
+            action: self.action,
+            // One after the other; both sh and cmd understand `&&`
+            command: (!commands.is_empty()).then(|| commands.join(" && ")),
+            status,
+            exit_code: None,
+            error: None,
+        };
+        Some((report, deferred))
+    }
+}
+
+// Runs the install command in the background, streaming its output as events.
+// If it fails, the deferred folders are copied from the source instead. Either
+// way the persisted Smart Sync report is updated and a final event is sent.
+pub fn spawn_install(app: AppHandle, worktree_path: String, command: String, deferred: Vec<PlannedItem>) {
+    std::thread::spawn(move || {
+        let result = run_install(&app, &worktree_path, &command);
+        let Ok(Some(mut report)) = smart_sync::load_report(&worktree_path) else {
+            println!("No Smart Sync report to update for {}", worktree_path);
+            return;
+        };
+
+        let status = match result {
+            Ok(()) => BootstrapStatus::Installed,
+            Err((exit_code, error)) => {
+                println!("Install in {} failed, copying dependencies instead: {}", worktree_path, error);
+                let mut fallback = deferred;
+                for item in &mut fallback {
+                    // Drop whatever the failed install left behind; it is a real folder inside the worktree
+                    let dest = Path::new(&item.destination);
+                    if dest.symlink_metadata().is_ok_and(|m| m.is_dir()) {
+                        let _ = std::fs::remove_dir_all(dest);
+                    }
+                    item.exists = dest.symlink_metadata().is_ok();
+                    item.strategy = SyncStrategy::Copy;
+                }
+
+                let mut copied = report.clone();
+                copied.items.clear();
+                smart_sync::sync_items(fallback, &mut copied, None);
+                for item in copied.items {
+                    match report.items.iter_mut().find(|i| i.destination == item.destination) {
+                        Some(existing) => *existing = item,
+                        None => report.items.push(item),
+                    }
+                }
+                if let Some(bootstrap) = report.bootstrap.as_mut() {
+                    bootstrap.exit_code = exit_code;
+                    bootstrap.error = Some(error);
+                }
+                BootstrapStatus::Copied
+            }
+        };
+
+        if let Some(bootstrap) = report.bootstrap.as_mut() {
+            bootstrap.status = status;
+        }
+        if let Err(e) = smart_sync::save_report(&report) {
+            println!("Failed to save Smart Sync report for {}: {}", worktree_path, e);
+        }
+        let _ = app.emit(BOOTSTRAP_EVENT, BootstrapEvent { worktree_path, line: None, status });
+    });
+}
+
+fn run_install(app: &AppHandle, worktree_path: &str, command: &str) -> Result<(), (Option<i32>, String)> {
+    #[cfg(target_os = "windows")]
+    let mut cmd = create_command("cmd");
+    #[cfg(target_os = "windows")]
+    cmd.arg("/C").arg(command);
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = create_command("sh");
+    #[cfg(not(target_os = "windows"))]
+    cmd.arg("-c").arg(command);
+
+    let mut child = cmd
+        .current_dir(worktree_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| (None, format!("Failed to start '{}': {}", command, e)))?;
+
+    let emit_line = |line: String| {
+        let _ = app.emit(BOOTSTRAP_EVENT, BootstrapEvent { worktree_path: worktree_path.to_string(), line: Some(line), status: BootstrapStatus::Running });
+    };
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let mut tail: Vec<String> = Vec::new();
+
+    std::thread::scope(|scope| {
+        if let Some(stdout) = stdout {
+            scope.spawn(|| BufReader::new(stdout).lines().map_while(Result::ok).for_each(emit_line));
+        }
+        if let Some(stderr) = stderr {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if tail.len() == ERROR_TAIL_LINES {
+                    tail.remove(0);
+                }
+                tail.push(line.clone());
+                emit_line(line);
+            }
+        }
+    });
+
+    let status = child.wait().map_err(|e| (None, e.to_string()))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err((status.code(), format!("'{}' failed: {}", command, tail.join("\n"))))
+    }
+}
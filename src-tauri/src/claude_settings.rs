@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use crate::error::AppError;
+
+// Claude events gwm-hook reports
+pub const HOOK_EVENTS: [&str; 4] = ["PermissionRequest", "PreToolUse", "PostToolUse", "Stop"];
+
+// Whether a hook command in Claude's settings was installed by us, possibly by an
+// earlier run (another secret, the app moved) or version (the PowerShell script).
+fn is_our_hook(command: &str) -> bool {
+    command.contains("gwm-hook\" --token") || command.contains("gwm-hook.exe\" --token") || command.contains("git-worktree-hook.ps1")
+}
+
+// Makes `command` the only hook of ours for `event`, adding it when missing and
+// dropping stale copies. Other hooks and their groups stay as they are.
+// Returns whether anything changed.
+fn merge_hook(hooks: &mut serde_json::Map<String, serde_json::Value>, event: &str, command: &str) -> Result<bool, String> {
+    let groups = hooks.entry(event).or_insert_with(|| serde_json::json!([]));
+    let groups = groups.as_array_mut().ok_or_else(|| format!("\"hooks.{}\" is not a list", event))?;
+
+    let mut found = false;
+    let mut changed = false;
+    let mut emptied = Vec::new();
+    for (i, group) in groups.iter_mut().enumerate() {
+        let Some(list) = group.get_mut("hooks").and_then(|h| h.as_array_mut()) else {
+            continue;
+        };
+        let before = list.len();
+        list.retain(|hook| match hook.get("command").and_then(|c| c.as_str()) {
+            // Keep the first current copy only
+            Some(c) if c == command => !std::mem::replace(&mut found, true),
+            Some(c) => !is_our_hook(c),
+            None => true,
+        });
+        if list.len() != before {
+            changed = true;
+            if list.is_empty() {
+                emptied.push(i);
+            }
+        }
+    }
+    // Groups that held nothing but stale hooks of ours
+    for i in emptied.into_iter().rev() {
+        groups.remove(i);
+    }
+
+    if !found {
+        // We omit "matcher" to apply to all events of that type
+        groups.push(serde_json::json!({ "hooks": [{ "type": "command", "command": command }] }));
+        changed = true;
+    }
+    Ok(changed)
+}
+
+// Registers `<cmd_base> <event>` for every event in the Claude settings file at
+// `settings_path`. Only our own entries are added or updated; a file that is not
+// valid JSON is left alone, and the previous version is kept as
+// settings.json.<unix seconds>.bak before every change. Returns whether the file changed.
+pub fn install_hooks(settings_path: &Path, cmd_base: &str) -> Result<bool, AppError> {
+    let invalid = |reason: String| {
+        AppError::HookInstall(format!("'{}' is not valid ({}); fix it and restart the app, it was not changed", settings_path.display(), reason))
+    };
+    let original = match std::fs::read_to_string(settings_path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(AppError::HookInstall(format!("Failed to read '{}': {}", settings_path.display(), e))),
+    };
+    let mut settings: serde_json::Value = match original.as_deref() {
+        Some(content) if !content.trim().is_empty() => serde_json::from_str(content).map_err(|e| invalid(e.to_string()))?,
+        _ => serde_json::json!({}),
+    };
+    let hooks = settings
+        .as_object_mut()
+        .ok_or_else(|| invalid("expected a JSON object".to_string()))?
+        .entry("hooks")
+        .or_insert_with(|| serde_json::json!({}))
+        .as_object_mut()
+        .ok_or_else(|| invalid("\"hooks\" is not an object".to_string()))?;
+
+    let mut changed = false;
+    for event in HOOK_EVENTS {
+        changed |= merge_hook(hooks, event, &format!("{} {}", cmd_base, event)).map_err(invalid)?;
+    }
+    if !changed {
+        return Ok(false);
+    }
+
+    if let Some(content) = &original {
+        let secs = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let backup = settings_path.with_file_name(format!("settings.json.{}.bak", secs));
+        std::fs::write(&backup, content).map_err(|e| AppError::HookInstall(format!("Failed to back up settings to '{}': {}", backup.display(), e)))?;
+        println!("Backed up Claude settings to {}", backup.display());
+    }
+    let new_content = serde_json::to_string_pretty(&settings).map_err(|e| AppError::HookInstall(e.to_string()))?;
+    std::fs::write(settings_path, new_content).map_err(|e| AppError::HookInstall(e.to_string()))?;
+    Ok(true)
+}
@@ -0,0 +1,197 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+// How a git process failed: its exit code (None when killed) and trimmed stderr.
+#[derive(Debug, Clone)]
+pub struct GitFailure {
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+impl GitFailure {
+    // The line saying why git gave up, without its "fatal: " prefix. Progress
+    // lines such as "Preparing worktree (new branch 'x')" come before it.
+    pub fn reason(&self) -> &str {
+        let line = self.stderr.lines().rev().find(|l| l.starts_with("fatal: ") || l.starts_with("error: "));
+        match line {
+            Some(line) => line.split_once(": ").map(|(_, reason)| reason).unwrap_or(line),
+            None => self.stderr.trim(),
+        }
+    }
+}
+
+// Error returned by every command. The frontend receives it as
+// { kind, message, exit_code, stderr, remediation } so it can tell failures
+// apart without parsing messages.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("Git is not installed or not on the PATH")]
+    GitNotInstalled,
+    #[error("'{path}' is not inside a git repository")]
+    NotARepository { path: String, git: GitFailure },
+    #[error("A branch named '{branch}' already exists")]
+    BranchExists { branch: String, git: GitFailure },
+    #[error("Branch '{branch}' is already checked out in another worktree")]
+    BranchCheckedOut { branch: String, git: GitFailure },
+    #[error("Branch '{branch}' is not fully merged")]
+    BranchNotMerged { branch: String, git: GitFailure },
+    // Found by git, or checked before running it
+    #[error("'{path}' already exists")]
+    PathExists { path: String, git: Option<GitFailure> },
+    // A removed worktree's branch now points somewhere else, so restoring it would undo those commits
+    #[error("Branch '{branch}' has moved since the worktree was removed")]
+    BranchMoved { branch: String },
+    #[error("Unknown branch, tag or commit: {}", git.reason())]
+    InvalidRef { git: GitFailure },
+    #[error("Worktree is locked: {}", git.reason())]
+    WorktreeLocked { git: GitFailure },
+    // Removing would lose work the safety check found (or git refused for the same reason)
+    #[error("Worktree has work that would be lost ({summary}). Review it or remove with force.")]
+    UnsafeRemoval { summary: String },
+    #[error("{}", git.stderr)]
+    Git { git: GitFailure },
+    #[error("'git {command}' timed out after {seconds}s")]
+    GitTimeout { command: String, seconds: u64 },
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("{message}")]
+    Io { message: String, source: std::io::Error },
+    // A file the app keeps (config, report, journal) that cannot be read back
+    #[error("'{path}' is invalid: {reason}")]
+    InvalidFile { path: String, reason: String },
+    #[error("{0}")]
+    NotFound(String),
+    #[error("Invalid Smart Sync pattern '{pattern}': {reason}")]
+    InvalidPattern { pattern: String, reason: String },
+    // A recorded link that resolves outside its worktree; never deleted or replaced
+    #[error("Refusing to change '{path}': it is outside the worktree '{worktree}'")]
+    OutsideWorktree { path: String, worktree: String },
+    #[error("No active Claude session found for '{path}'")]
+    NoSession { path: String },
+    #[error("{0}")]
+    Session(String),
+    #[error("Failed to install Claude hooks: {0}")]
+    HookInstall(String),
+    #[error("{0} is currently Windows only")]
+    Unsupported(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AppError {
+    // An IO error with what was being done when it happened.
+    pub fn io(context: impl std::fmt::Display, source: std::io::Error) -> Self {
+        AppError::Io { message: format!("{}: {}", context, source), source }
+    }
+
+    // Sorts a failed git run by what its (untranslated, see git::Git) stderr says.
+    pub fn from_git(dir: &std::path::Path, git: GitFailure) -> Self {
+        let stderr = git.reason();
+        // Names in git's messages are the first quoted word
+        let quoted = || stderr.split('\'').nth(1).unwrap_or_default().to_string();
+        if stderr.contains("not a git repository") {
+            AppError::NotARepository { path: dir.display().to_string(), git }
+        } else if stderr.contains("a branch named") && stderr.contains("already exists") {
+            AppError::BranchExists { branch: quoted(), git }
+        } else if stderr.contains("is already checked out at") || stderr.contains("is already used by worktree at") {
+            AppError::BranchCheckedOut { branch: quoted(), git }
+        } else if stderr.contains("is not fully merged") {
+            AppError::BranchNotMerged { branch: quoted(), git }
+        } else if stderr.contains("already exists") {
+            AppError::PathExists { path: quoted(), git: Some(git) }
+        } else if stderr.contains("invalid reference")
+            || stderr.contains("not a valid object name")
+            || stderr.contains("unknown revision")
+            || stderr.contains("bad revision")
+            || stderr.contains("not a commit")
+        {
+            AppError::InvalidRef { git }
+        } else if stderr.contains("locked working tree") || stderr.contains("is locked") {
+            AppError::WorktreeLocked { git }
+        } else {
+            AppError::Git { git }
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::GitNotInstalled => "git_not_installed",
+            AppError::NotARepository { .. } => "not_a_repository",
+            AppError::BranchExists { .. } => "branch_exists",
+            AppError::BranchCheckedOut { .. } => "branch_checked_out",
+            AppError::BranchNotMerged { .. } => "branch_not_merged",
+            AppError::PathExists { .. } => "path_exists",
+            AppError::BranchMoved { .. } => "branch_moved",
+            AppError::InvalidRef { .. } => "invalid_ref",
+            AppError::WorktreeLocked { .. } => "worktree_locked",
+            AppError::UnsafeRemoval { .. } => "unsafe_removal",
+            AppError::Git { .. } => "git",
+            AppError::GitTimeout { .. } => "git_timeout",
+            AppError::Cancelled => "cancelled",
+            AppError::Io { .. } => "io",
+            AppError::InvalidFile { .. } => "invalid_file",
+            AppError::NotFound(_) => "not_found",
+            AppError::InvalidPattern { .. } => "invalid_pattern",
+            AppError::OutsideWorktree { .. } => "outside_worktree",
+            AppError::NoSession { .. } => "no_session",
+            AppError::Session(_) => "session",
+            AppError::HookInstall(_) => "hook_install",
+            AppError::Unsupported(_) => "unsupported",
+            AppError::Other(_) => "other",
+        }
+    }
+
+    fn git_failure(&self) -> Option<&GitFailure> {
+        match self {
+            AppError::NotARepository { git, .. }
+            | AppError::BranchExists { git, .. }
+            | AppError::BranchCheckedOut { git, .. }
+            | AppError::BranchNotMerged { git, .. }
+            | AppError::InvalidRef { git }
+            | AppError::WorktreeLocked { git }
+            | AppError::Git { git } => Some(git),
+            AppError::PathExists { git, .. } => git.as_ref(),
+            _ => None,
+        }
+    }
+
+    // What the user can do about it, when there is something to suggest.
+    pub fn remediation(&self) -> Option<&'static str> {
+        Some(match self {
+            AppError::GitNotInstalled => "Install Git, make sure `git` is on the PATH, then restart the app.",
+            AppError::NotARepository { .. } => "Select the folder of a git repository or one of its worktrees.",
+            AppError::BranchExists { .. } => "Pick another branch name, or create the worktree from the existing branch.",
+            AppError::BranchCheckedOut { .. } => "Use another branch, or remove the worktree that has this one checked out.",
+            AppError::BranchNotMerged { .. } => "The worktree is removed but the branch was kept. Merge it, or delete it with `git branch -D` if its commits can go.",
+            AppError::PathExists { .. } => "Choose a folder that does not exist yet, or move the existing one out of the way.",
+            AppError::BranchMoved { .. } => "Rename or delete the branch to restore the removed worktree's commits under that name.",
+            AppError::InvalidRef { .. } => "Check the base branch or commit; fetch first if it only exists on a remote.",
+            AppError::WorktreeLocked { .. } => "Unlock it with `git worktree unlock` before removing it.",
+            AppError::UnsafeRemoval { .. } => "Commit or stash the changes, or remove with force (they stay restorable from Recently Removed).",
+            AppError::GitTimeout { .. } => "Check for a stuck git process (e.g. waiting on credentials) and try again.",
+            AppError::InvalidFile { .. } => "Fix or delete the file; the app recreates it with defaults.",
+            AppError::InvalidPattern { .. } => "Fix the rule in the Smart Sync settings; patterns follow .gitignore syntax.",
+            AppError::NoSession { .. } => "Open a new Claude session for this worktree.",
+            AppError::HookInstall(_) => "Check that ~/.claude exists and settings.json is writable valid JSON.",
+            _ => return None,
+        })
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 5)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        let git = self.git_failure();
+        state.serialize_field("exit_code", &git.and_then(|g| g.exit_code))?;
+        state.serialize_field("stderr", &git.map(|g| g.stderr.as_str()))?;
+        state.serialize_field("remediation", &self.remediation())?;
+        state.end()
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(source: std::io::Error) -> Self {
+        AppError::Io { message: source.to_string(), source }
+    }
+}
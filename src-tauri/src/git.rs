@@ -0,0 +1,162 @@
+use std::ffi::{OsStr, OsString};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+use crate::create_command;
+use crate::error::{AppError, GitFailure};
+use crate::walk::CancelFlag;
+
+// Git processes allowed to run at once across the whole app
+const MAX_CONCURRENT: usize = 8;
+
+// Queries (status, rev-parse, log...) answer quickly; one that hangs is killed
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Checkouts and removals touch every file of the worktree
+pub const CHECKOUT_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+// How often a running git is checked for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT);
+
+// One git invocation. Every git call in the app goes through here so they all
+// share the concurrency limit, the timeout and the environment: git never
+// prompts (nobody would see the prompt, so it would hang until the timeout)
+// and its messages are untranslated, since callers parse them.
+pub struct Git {
+    dir: PathBuf,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    timeout: Duration,
+    cancel: Option<CancelFlag>,
+}
+
+impl Git {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Git {
+            dir: dir.as_ref().to_path_buf(),
+            args: Vec::new(),
+            envs: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            cancel: None,
+        }
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<S: AsRef<OsStr>>(mut self, args: impl IntoIterator<Item = S>) -> Self {
+        self.args.extend(args.into_iter().map(|a| a.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.envs.push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // Kills git as soon as `cancel` is set; the call then fails with AppError::Cancelled.
+    pub fn cancel_on(mut self, cancel: CancelFlag) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    // Runs git to completion whatever its exit status. Fails only when git
+    // could not be started, ran out of time or was cancelled.
+    pub async fn output(self) -> Result<Output, AppError> {
+        let _permit = PERMITS.acquire().await.map_err(|e| AppError::Other(e.to_string()))?;
+        if self.cancel.as_ref().is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return Err(AppError::Cancelled);
+        }
+
+        let mut cmd = tokio::process::Command::from(create_command("git"));
+        cmd.current_dir(&self.dir)
+            .args(&self.args)
+            .envs(self.envs.iter().map(|(k, v)| (k, v)))
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env("LC_ALL", "C")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Dropping the child below (timeout, cancellation) kills git
+            .kill_on_drop(true);
+        let child = cmd.spawn().map_err(|e| match e.kind() {
+            // A missing working directory also fails with NotFound
+            _ if !self.dir.is_dir() => AppError::NotFound(format!("Folder '{}' does not exist", self.dir.display())),
+            std::io::ErrorKind::NotFound => AppError::GitNotInstalled,
+            _ => AppError::io(format!("Failed to execute git command at '{}'", self.dir.display()), e),
+        })?;
+
+        tokio::select! {
+            result = tokio::time::timeout(self.timeout, child.wait_with_output()) => match result {
+                Ok(output) => Ok(output?),
+                Err(_) => Err(AppError::GitTimeout { command: self.describe(), seconds: self.timeout.as_secs() }),
+            },
+            _ = cancelled(self.cancel.as_ref()) => Err(AppError::Cancelled),
+        }
+    }
+
+    // Stdout of a successful run; otherwise the failure, classified from git's stderr.
+    pub async fn run(self) -> Result<String, AppError> {
+        let dir = self.dir.clone();
+        let output = self.output().await?;
+        if !output.status.success() {
+            return Err(AppError::from_git(&dir, failure(&output)));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    // `output` for code that is not async (Smart Sync's file work, background threads).
+    pub fn output_blocking(self) -> Result<Output, AppError> {
+        block_on(self.output())
+    }
+
+    // `run` for code that is not async.
+    pub fn run_blocking(self) -> Result<String, AppError> {
+        block_on(self.run())
+    }
+
+    fn describe(&self) -> String {
+        self.args.iter().map(|a| a.to_string_lossy()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+// Exit code and trimmed stderr of a failed git (its exit status when it printed nothing).
+pub fn failure(output: &Output) -> GitFailure {
+    let mut stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if stderr.is_empty() {
+        stderr = format!("git exited with {}", output.status);
+    }
+    GitFailure { exit_code: output.status.code(), stderr }
+}
+
+async fn cancelled(cancel: Option<&CancelFlag>) {
+    match cancel {
+        Some(cancel) => {
+            while !cancel.load(Ordering::Relaxed) {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+        None => std::future::pending().await,
+    }
+}
+
+// Waits for `future` from synchronous code, whether or not the thread belongs to the runtime.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(future)),
+        Err(_) => tauri::async_runtime::block_on(future),
+    }
+}
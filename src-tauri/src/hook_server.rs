@@ -0,0 +1,366 @@
+use axum::{
+    body::Bytes,
+    extract::{Request, State as AxumState},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::UnixListener;
+
+use crate::error::AppError;
+
+// Port tried first when GWM_HOOK_PORT is not set; another one is picked if it is taken
+const DEFAULT_PORT: u16 = 36911;
+
+// Environment variable fixing the port ("0" lets the OS choose)
+pub const PORT_ENV: &str = "GWM_HOOK_PORT";
+
+// Event telling the UI whether Claude status updates can reach this window.
+const STATUS_EVENT: &str = "hook-server-status";
+
+// Answer of /health, so a second instance can recognize the first one
+const APP_ID: &str = "git-worktree-manager";
+
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+// Random bytes in the hook secret (hex encoded in the file and the hook command)
+const SECRET_BYTES: usize = 32;
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ServerStatus {
+    #[default]
+    Starting,
+    // `socket` is the Unix domain socket the hooks prefer, when one could be created
+    Running { port: u16, socket: Option<String> },
+    Failed { error: String },
+    // Another instance of the app receives the hooks; this one gets no Claude status updates
+    OtherInstance { pid: u32, port: u16 },
+}
+
+#[derive(Default)]
+pub struct HookServer(Mutex<ServerStatus>);
+
+// Where the running app can be reached. Written at startup; the installed hooks
+// read it on every call, so the port may change between runs.
+#[derive(Debug, Serialize, Deserialize)]
+struct Discovery {
+    pid: u32,
+    port: u16,
+    url: String,
+    // Unix domain socket serving the same routes; hooks use it over `url` when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    socket: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct HookPayload {
+    path: String,
+    status: String, // "waiting_auth", "running", "idle"
+    message: Option<String>
+}
+
+struct ServerState {
+    app_handle: AppHandle,
+    // Hooks send it as "Authorization: Bearer <secret>"; anything else is rejected
+    secret: String,
+}
+
+// Where Claude Code keeps its settings and hooks (~/.claude).
+pub fn claude_dir() -> Result<PathBuf, AppError> {
+    #[cfg(target_os = "windows")]
+    let home = std::env::var("USERPROFILE").map_err(|_| AppError::HookInstall("Could not find USERPROFILE".to_string()))?;
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var("HOME").map_err(|_| AppError::HookInstall("Could not find HOME".to_string()))?;
+    Ok(PathBuf::from(home).join(".claude"))
+}
+
+fn discovery_path() -> Result<PathBuf, AppError> {
+    Ok(claude_dir()?.join("hooks").join("git-worktree-manager.json"))
+}
+
+fn write_discovery(port: u16, socket: Option<&Path>) -> Result<(), AppError> {
+    let path = discovery_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let discovery = Discovery {
+        pid: std::process::id(),
+        port,
+        url: format!("http://127.0.0.1:{}/claude/status", port),
+        socket: socket.map(|s| s.to_string_lossy().to_string()),
+    };
+    let content = serde_json::to_string_pretty(&discovery).map_err(|e| AppError::Other(e.to_string()))?;
+    // Hooks may read it at any moment, so replace it in one step
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, content).map_err(|e| AppError::io(format!("Failed to write '{}'", tmp.display()), e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| AppError::io(format!("Failed to write '{}'", path.display()), e))
+}
+
+fn secret_path() -> Result<PathBuf, AppError> {
+    Ok(claude_dir()?.join("hooks").join("git-worktree-manager.secret"))
+}
+
+// The secret shared with the installed hooks, created on first use and kept
+// afterwards so hooks installed earlier keep working.
+pub fn hook_secret() -> Result<String, AppError> {
+    // The installer and the server both ask for it at startup
+    static LOCK: Mutex<()> = Mutex::new(());
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let path = secret_path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(secret) if secret.trim().len() == SECRET_BYTES * 2 => return Ok(secret.trim().to_string()),
+        Ok(_) => println!("Replacing malformed hook secret {}", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(AppError::io(format!("Failed to read '{}'", path.display()), e)),
+    }
+
+    let mut bytes = [0u8; SECRET_BYTES];
+    getrandom::fill(&mut bytes).map_err(|e| AppError::HookInstall(format!("Failed to generate the hook secret: {}", e)))?;
+    let secret: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // Readable by the user only
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(secret.as_bytes()))
+        .map_err(|e| AppError::io(format!("Failed to write '{}'", path.display()), e))?;
+    Ok(secret)
+}
+
+#[tauri::command]
+pub fn hook_server_status(state: State<'_, HookServer>) -> ServerStatus {
+    state.0.lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+fn set_status(app: &AppHandle, status: ServerStatus) {
+    println!("Hook server: {:?}", status);
+    if let Ok(mut current) = app.state::<HookServer>().0.lock() {
+        *current = status.clone();
+    }
+    let _ = app.emit(STATUS_EVENT, status);
+}
+
+// Starts the local server Claude's hooks post to, unless another instance already runs one.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Some(other) = running_instance().await {
+            set_status(&app, ServerStatus::OtherInstance { pid: other.pid, port: other.port });
+            return;
+        }
+
+        let listener = match bind().await {
+            Ok(listener) => listener,
+            Err(e) => return set_status(&app, ServerStatus::Failed { error: e.to_string() }),
+        };
+        let port = match listener.local_addr() {
+            Ok(addr) => addr.port(),
+            Err(e) => return set_status(&app, ServerStatus::Failed { error: e.to_string() }),
+        };
+        let secret = match hook_secret() {
+            Ok(secret) => secret,
+            Err(e) => return set_status(&app, ServerStatus::Failed { error: e.to_string() }),
+        };
+
+        let state = Arc::new(ServerState { app_handle: app.clone(), secret });
+        let router = Router::new()
+            .route("/claude/status", post(hook_handler))
+            .route("/health", get(health))
+            .layer(middleware::from_fn(local_only))
+            .with_state(state);
+
+        // Same routes on a socket only this user can open; TCP stays for hooks that cannot use it
+        #[cfg(unix)]
+        let socket = socket_path().and_then(|path| match bind_socket(&path) {
+            Ok(socket_listener) => {
+                let router = router.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = axum::serve(socket_listener, router).await {
+                        println!("Hook server: socket stopped: {}", e);
+                    }
+                });
+                Some(path)
+            }
+            Err(e) => {
+                println!("Hook server: no Unix socket, hooks will use TCP: {}", e);
+                None
+            }
+        });
+        #[cfg(not(unix))]
+        let socket: Option<PathBuf> = None;
+
+        if let Err(e) = write_discovery(port, socket.as_deref()) {
+            return set_status(&app, ServerStatus::Failed { error: format!("Hooks cannot find the server: {}", e) });
+        }
+        set_status(&app, ServerStatus::Running { port, socket: socket.map(|s| s.to_string_lossy().to_string()) });
+
+        if let Err(e) = axum::serve(listener, router).await {
+            set_status(&app, ServerStatus::Failed { error: e.to_string() });
+        }
+    });
+}
+
+async fn bind() -> Result<TcpListener, AppError> {
+    let bind_port = |port: u16| async move {
+        TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| AppError::io(format!("Failed to listen on 127.0.0.1:{}", port), e))
+    };
+    match std::env::var(PORT_ENV) {
+        Ok(port) => {
+            let port = port.trim().parse().map_err(|_| AppError::Other(format!("{} must be a port number, got '{}'", PORT_ENV, port)))?;
+            bind_port(port).await
+        }
+        // Taken by some other program: let the OS pick, the discovery file tells the hooks
+        Err(_) => match bind_port(DEFAULT_PORT).await {
+            Ok(listener) => Ok(listener),
+            Err(_) => bind_port(0).await,
+        },
+    }
+}
+
+// $XDG_RUNTIME_DIR/git-worktree-manager/hooks.sock, if the session has a runtime directory.
+#[cfg(unix)]
+fn socket_path() -> Option<PathBuf> {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty())?;
+    Some(PathBuf::from(runtime_dir).join(APP_ID).join("hooks.sock"))
+}
+
+#[cfg(unix)]
+fn bind_socket(path: &Path) -> Result<UnixListener, AppError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = path.parent().ok_or_else(|| AppError::Other(format!("Invalid socket path '{}'", path.display())))?;
+    std::fs::create_dir_all(dir).map_err(|e| AppError::io(format!("Failed to create '{}'", dir.display()), e))?;
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+        .map_err(|e| AppError::io(format!("Failed to restrict '{}'", dir.display()), e))?;
+    // Left behind by an instance that did not exit cleanly (a live one was ruled out before)
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(AppError::io(format!("Failed to remove stale socket '{}'", path.display()), e));
+        }
+        _ => {}
+    }
+    let listener = UnixListener::bind(path).map_err(|e| AppError::io(format!("Failed to listen on '{}'", path.display()), e))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| AppError::io(format!("Failed to restrict '{}'", path.display()), e))?;
+    Ok(listener)
+}
+
+// The instance named in the discovery file, if it is still up and answering.
+async fn running_instance() -> Option<Discovery> {
+    let content = std::fs::read_to_string(discovery_path().ok()?).ok()?;
+    let discovery: Discovery = serde_json::from_str(&content).ok()?;
+    if discovery.pid == std::process::id() {
+        return None;
+    }
+    let reply = tokio::time::timeout(PROBE_TIMEOUT, probe(discovery.port)).await.ok()?.ok()?;
+    reply.contains(APP_ID).then_some(discovery)
+}
+
+async fn probe(port: u16) -> std::io::Result<String> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await?;
+    let request = format!("GET /health HTTP/1.0\r\nHost: 127.0.0.1:{}\r\n\r\n", port);
+    stream.write_all(request.as_bytes()).await?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).await?;
+    Ok(reply)
+}
+
+// Logs and answers a request the server refuses.
+fn reject(route: &str, status: StatusCode, reason: &str) -> Response {
+    println!("Hook server: rejected {} ({}): {}", route, status.as_u16(), reason);
+    (status, reason.to_string()).into_response()
+}
+
+// Only the hooks, which run on this machine and are not browsers, may talk to the
+// server. A web page can still reach 127.0.0.1, directly or through a DNS name
+// rebound to it; browsers always send its Origin and the name it used as Host.
+async fn local_only(request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    let host = headers.get(header::HOST).and_then(|h| h.to_str().ok()).unwrap_or_default();
+    let reason = if let Some(origin) = headers.get(header::ORIGIN) {
+        format!("cross-origin request from {:?}", origin)
+    } else if !is_loopback_host(host) {
+        format!("unexpected Host '{}'", host)
+    } else {
+        return next.run(request).await;
+    };
+    reject(&format!("{} {}", request.method(), request.uri()), StatusCode::FORBIDDEN, &reason)
+}
+
+// "127.0.0.1", "localhost" or "[::1]", with or without a port.
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    matches!(name.to_ascii_lowercase().as_str(), "127.0.0.1" | "localhost" | "[::1]")
+}
+
+// Compares in constant time, so the secret cannot be guessed byte by byte from response times.
+fn same_secret(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "app": APP_ID, "pid": std::process::id() }))
+}
+
+async fn hook_handler(
+    AxumState(state): AxumState<Arc<ServerState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    const ROUTE: &str = "POST /claude/status";
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::trim);
+    match token {
+        None => return reject(ROUTE, StatusCode::UNAUTHORIZED, "missing hook secret"),
+        Some(token) if !same_secret(token, &state.secret) => return reject(ROUTE, StatusCode::UNAUTHORIZED, "wrong hook secret"),
+        Some(_) => {}
+    }
+    // Parsed only once the caller is known to be one of our hooks
+    let payload: HookPayload = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => return reject(ROUTE, StatusCode::BAD_REQUEST, &format!("invalid payload: {}", e)),
+    };
+
+    println!("=== Hook Received ===");
+    println!("Path: '{}'", payload.path);
+    println!("Status: {}", payload.status);
+    println!("====================");
+
+    let _ = state.app_handle.emit("claude-status-change", &payload);
+
+    if payload.status == "waiting_auth" {
+         let _ = state.app_handle.notification()
+            .builder()
+            .title("Claude Permission Request")
+            .body("Claude is waiting for your approval.")
+            .show();
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
@@ -2,20 +2,30 @@ use std::process::Command;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use std::sync::Mutex;
-use tauri::{State, Manager, Emitter};
-use std::sync::Arc;
-use axum::{
-    routing::post,
-    Router,
-    Json,
-    extract::State as AxumState,
-};
-use tauri_plugin_notification::NotificationExt;
+use tauri::State;
+
+pub mod backend;
+mod bootstrap;
+pub mod claude_settings;
+mod error;
+mod git;
+mod hook_server;
+mod libgit2;
+mod progress;
+pub mod safety;
+pub mod smart_sync;
+mod status;
+mod trash;
+pub mod walk;
+mod watcher;
+
+use error::AppError;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
 fn create_command(program: &str) -> Command {
+    #[allow(unused_mut)]
     let mut cmd = Command::new(program);
     #[cfg(target_os = "windows")]
     {
@@ -24,344 +34,304 @@ fn create_command(program: &str) -> Command {
     cmd
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// Runs git in `dir` and returns its stdout, or stderr as the error message.
+// Blocks the calling thread; async code awaits `git::Git` directly.
+fn run_git<S: AsRef<std::ffi::OsStr>>(dir: &str, args: &[S]) -> Result<String, AppError> {
+    git::Git::new(dir).args(args).run_blocking()
+}
+
+// Runs synchronous command code (file system work, blocking git calls) on the
+// blocking pool, so neither the UI thread nor the async runtime waits on it.
+async fn run_blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f).await.map_err(|e| AppError::Other(e.to_string()))?
+}
+
+// The git dir shared by every worktree of the repo (`.git` of the main checkout).
+fn common_dir(project_path: &str) -> Result<std::path::PathBuf, AppError> {
+    let common_dir = run_git(project_path, &["rev-parse", "--path-format=absolute", "--git-common-dir"])?;
+    Ok(std::path::PathBuf::from(common_dir.trim()))
+}
+
+// Per-repo app state lives in the shared git dir, so every worktree of the repo sees it.
+fn manager_dir(project_path: &str) -> Result<std::path::PathBuf, AppError> {
+    Ok(common_dir(project_path)?.join("worktree-manager"))
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Worktree {
     path: String,
     head_hash: String,
     branch: Option<String>,
+    is_bare: bool,
+    is_detached: bool,
+    is_locked: bool,
+    lock_reason: Option<String>,
+    is_prunable: bool,
+    prunable_reason: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct HookPayload {
-    path: String,
-    status: String, // "waiting_auth", "running", "idle"
-    message: Option<String>
+#[tauri::command]
+async fn list_worktrees(project_path: String) -> Result<Vec<Worktree>, AppError> {
+    run_blocking(move || backend::current().list_worktrees(std::path::Path::new(&project_path))).await
 }
 
-struct ServerState {
-    app_handle: tauri::AppHandle,
+// Local and remote-tracking branches, suggested when picking a branch or base.
+#[tauri::command]
+async fn list_branches(project_path: String) -> Result<Vec<backend::Branch>, AppError> {
+    run_blocking(move || backend::current().branches(std::path::Path::new(&project_path))).await
 }
 
-fn parse_worktrees(output: &str) -> Vec<Worktree> {
-    let mut worktrees = Vec::new();
-    let mut current_worktree = Worktree {
-        path: String::new(),
-        head_hash: String::new(),
-        branch: None,
-    };
-    let mut has_data = false;
-
-    for line in output.lines() {
-        if line.is_empty() {
-             if has_data {
-                worktrees.push(current_worktree);
-                current_worktree = Worktree {
-                    path: String::new(),
-                    head_hash: String::new(),
-                    branch: None,
-                };
-                has_data = false;
-             }
-             continue;
-        }
-        
-        if line.starts_with("worktree ") {
-             current_worktree.path = line[9..].to_string();
-             has_data = true;
-        } else if line.starts_with("HEAD ") {
-             current_worktree.head_hash = line[5..].to_string();
-        } else if line.starts_with("branch ") {
-             let ref_name = &line[7..];
-             let branch_name = ref_name.strip_prefix("refs/heads/").unwrap_or(ref_name);
-             current_worktree.branch = Some(branch_name.to_string());
-        }
-    }
-    if has_data {
-        worktrees.push(current_worktree);
-    }
-    worktrees
+// How `create_worktree` obtains the branch it checks out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CreateMode {
+    // Create `branch` from `base` (or HEAD)
+    #[default]
+    NewBranch,
+    // Check out a local `branch` that already exists
+    ExistingBranch,
+    // Create `branch` tracking the remote ref in `base` (defaults to origin/<branch>)
+    TrackRemote,
+    // Detached HEAD at `base` (or `branch` when no base is given)
+    Detached,
 }
 
+// Creates the worktree off the UI thread, reporting progress under `operation_id`.
+// Cancelling it rolls the partially created worktree back.
 #[tauri::command]
-fn list_worktrees(project_path: String) -> Result<Vec<Worktree>, String> {
-    let output = create_command("git")
-        .arg("worktree")
-        .arg("list")
-        .arg("--porcelain")
-        .current_dir(&project_path)
-        .output()
-        .map_err(|e| format!("Failed to execute git command at '{}': {}", project_path, e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Git Error at '{}': {}", project_path, stderr));
-    }
-
-    Ok(parse_worktrees(&String::from_utf8_lossy(&output.stdout)))
+#[allow(clippy::too_many_arguments)]
+async fn create_worktree(
+    app: tauri::AppHandle,
+    operations: State<'_, progress::Operations>,
+    project_path: String,
+    path: String,
+    branch: String,
+    base: Option<String>,
+    smart_sync: bool,
+    mode: Option<CreateMode>,
+    sync_source: Option<String>,
+    operation_id: Option<String>,
+) -> Result<Option<smart_sync::SyncReport>, AppError> {
+    let op = operations.begin(app, operation_id);
+    run_blocking(move || {
+        add_worktree(&op, &project_path, &path, &branch, base, smart_sync, mode.unwrap_or_default(), sync_source)
+    })
+    .await
 }
 
-use walkdir::{WalkDir, DirEntry};
-use std::collections::HashSet;
-
-fn link_gitignored_items(project_path: &str, worktree_path: &str) {
-    let project_dir = std::path::Path::new(project_path);
-    let worktree_dir = std::path::Path::new(worktree_path);
-    let gitignore_path = project_dir.join(".gitignore");
-
-    println!("Scanning for recursive ignore targets via .gitignore...");
-
-    let mut ignore_targets = HashSet::new();
-    // Default commons just in case (optional, but requested based on .gitignore)
-    // ignore_targets.insert("node_modules".to_string());
-    // ignore_targets.insert(".env".to_string());
-
-    if gitignore_path.exists() {
-         if let Ok(content) = std::fs::read_to_string(&gitignore_path) {
-             for line in content.lines() {
-                 let line = line.trim();
-                 if line.is_empty() || line.starts_with('#') { continue; }
-                 
-                 // Clean up pattern: /node_modules -> node_modules, node_modules/ -> node_modules
-                 let clean = line.replace('\\', "/");
-                 let clean = clean.trim_matches('/');
-                 
-                 // We collect names. Simple logic: if a dir matches this name, we link it.
-                 // This covers standard "node_modules" rules.
-                 // Complex globs like "*.log" are harder with this approach but simple names cover 90% of cases.
-                 if !clean.is_empty() {
-                    ignore_targets.insert(clean.to_string());
-                 }
-             }
-         }
-    } else {
-        println!("No .gitignore found, skipping auto-link.");
-        return;
-    }
-    
-    if ignore_targets.is_empty() {
-        return;
-    }
-
-    println!("Targets to link: {:?}", ignore_targets);
+#[allow(clippy::too_many_arguments)]
+fn add_worktree(op: &progress::Operation, project_path: &str, path: &str, branch: &str, base: Option<String>, smart_sync: bool, mode: CreateMode, sync_source: Option<String>) -> Result<Option<smart_sync::SyncReport>, AppError> {
+    let base = base.filter(|b| !b.is_empty());
+    let mut cmd = git::Git::new(project_path)
+        .arg("worktree")
+        .arg("add");
 
-    // Recursive walk
-    let walker = WalkDir::new(project_dir).min_depth(1).into_iter();
-    
-    // We strictly want to skip descending into the directories we link, 
-    // AND skip hidden git dirs to save time.
-    fn is_ignored(entry: &DirEntry, targets: &HashSet<String>) -> bool {
-        entry.file_name()
-             .to_str()
-             .map(|s| targets.contains(s) || s == ".git" || s == ".vs" || s == "node_modules") 
-             // We check node_modules explicitly here to ensure we don't scan INSIDE a source node_modules if we missed adding it to targets
-             .unwrap_or(false)
+    match mode {
+        CreateMode::NewBranch => {
+            cmd = cmd.arg("-b").arg(branch).arg(path);
+            if let Some(b) = base {
+                cmd = cmd.arg(b);
+            }
+        }
+        CreateMode::ExistingBranch => {
+            cmd = cmd.arg(path).arg(branch);
+        }
+        CreateMode::TrackRemote => {
+            // --track sets branch.<name>.remote/merge so the upstream is ready to pull/push
+            let remote_ref = base.unwrap_or_else(|| format!("origin/{}", branch));
+            cmd = cmd.arg("--track").arg("-b").arg(branch).arg(path).arg(remote_ref);
+        }
+        CreateMode::Detached => {
+            cmd = cmd.arg("--detach").arg(path).arg(base.unwrap_or_else(|| branch.to_string()));
+        }
     }
+    // Branches this call creates go away again on rollback
+    let created_branch = matches!(mode, CreateMode::NewBranch | CreateMode::TrackRemote).then_some(branch);
+
+    op.report(progress::Phase::Checkout, 0, None, Some(path));
+    let output = match cmd.timeout(git::CHECKOUT_TIMEOUT).cancel_on(op.cancel_flag()).output_blocking() {
+        Ok(output) => output,
+        Err(e) => {
+            if op.is_cancelled() {
+                // git may have been killed halfway through the checkout
+                roll_back_creation(op, project_path, path, created_branch);
+            }
+            return Err(e);
+        }
+    };
 
-    // Custom filtering loop to control skipping
-    let mut it = walker.filter_entry(|e| !is_ignored(e, &ignore_targets));
-
-    // Wait, filter_entry skips descending but DOES yield the entry itself.
-    // Actually we want:
-    // 1. Visit entry.
-    // 2. If entry matches target -> Link it -> Skip recursion.
-    // 3. If entry is .git -> Skip recursion.
-    // 4. Else -> recurse.
-    
-    // WalkDir's filter_entry is: if false, skip directory.
-    // But we need to distinguish "Link this dir and stop" vs "Ignore this dir".
-    // Let's iterate manually using standard recursive iterator logic or just loop WalkDir without filter_entry 
-    // and manually skip? No, WalkDir provides skip_current_dir().
-
-    let mut iterator = WalkDir::new(project_dir).min_depth(1).into_iter();
-
-    // Collect failed links to run in batch
-    let mut pending_admin_links = Vec::new();
+    if !output.status.success() {
+        return Err(AppError::from_git(std::path::Path::new(project_path), git::failure(&output)));
+    }
 
-    loop {
-        let entry = match iterator.next() {
-            None => break,
-            Some(Err(_)) => continue,
-            Some(Ok(e)) => e,
+    // Auto-link gitignored files if enabled, from the chosen worktree or the one whose lockfiles match best
+    let mut report = None;
+    if smart_sync && !op.is_cancelled() {
+        let source = match sync_source.filter(|s| !s.is_empty()) {
+            Some(source) => source,
+            None => smart_sync::pick_source(project_path, path),
         };
+        report = Some(smart_sync::link_gitignored_items(op, &source, path));
+    }
 
-        let file_name = entry.file_name().to_string_lossy().to_string();
-        
-        // Safety skip
-        if file_name == ".git" {
-            iterator.skip_current_dir();
-            continue;
-        }
-
-        if ignore_targets.contains(&file_name) {
-            let src_path = entry.path();
-            
-            if let Ok(rel) = src_path.strip_prefix(project_dir) {
-                let dest_path = worktree_dir.join(rel);
-                
-                if let Some(parent) = dest_path.parent() {
-                    let _ = std::fs::create_dir_all(parent);
-                }
+    if op.is_cancelled() {
+        roll_back_creation(op, project_path, path, created_branch);
+        return Err(AppError::Cancelled);
+    }
+    Ok(report)
+}
 
-                if !dest_path.exists() {
-                     #[cfg(target_os = "windows")]
-                     {
-                        if src_path.is_dir() {
-                             let dest_str = dest_path.display().to_string().replace("/", "\\");
-                             let src_str = src_path.display().to_string().replace("/", "\\");
-                             let cmd_str = format!("mklink /J \"{}\" \"{}\"", dest_str, src_str);
-                             
-                             let output = create_command("cmd").arg("/C").arg(&cmd_str).output();
-                             let mut success = false;
-                             if let Ok(o) = output {
-                                 if o.status.success() {
-                                     println!("Linked: {} -> {}", src_path.display(), dest_path.display());
-                                     success = true;
-                                 }
-                             }
-                             
-                             if !success {
-                                 println!("Requires Admin: {}", dest_path.display());
-                                 pending_admin_links.push(cmd_str);
-                             }
-                        } else {
-                            // File Fallback Chain
-                            let _ = std::os::windows::fs::symlink_file(src_path, &dest_path)
-                                .or_else(|_| std::fs::hard_link(src_path, &dest_path))
-                                .or_else(|_| std::fs::copy(src_path, &dest_path).map(|_| ()));
-                        }
-                     }
-                }
-            }
-            if entry.file_type().is_dir() {
-                iterator.skip_current_dir();
-            }
+// Undoes a cancelled creation: links first (so nothing outside the worktree is
+// touched), then the worktree, its directory and the branch made for it.
+fn roll_back_creation(op: &progress::Operation, project_path: &str, path: &str, created_branch: Option<&str>) {
+    op.report(progress::Phase::RollBack, 0, None, Some(path));
+    let dir = std::path::Path::new(path);
+    if dir.exists() {
+        if let Err(e) = smart_sync::unlink_before_removal(path) {
+            // Deleting the directory could reach through a link; leave it for the user
+            println!("Rollback of {} stopped: {}", path, e);
+            return;
         }
     }
-    
-    // Batch Execute Admin Links
-    if !pending_admin_links.is_empty() {
-        println!("requesting admin for {} items...", pending_admin_links.len());
-        
-        // Use PowerShell script instead of Batch to handle Encoding/Unicode correctly.
-        // We prepend the UTF-8 BYTE ORDER MARK (BOM) so PowerShell explicitly knows it's UTF-8.
-        let mut ps1_content = String::from("\u{FEFF}"); 
-        ps1_content.push_str("$ErrorActionPreference = 'Stop'\n");
-
-        for cmd in pending_admin_links {
-            // cmd contains: mklink /J "dest" "src"
-            // In PowerShell, we run this via cmd /c. 
-            // We need to escape quotes if necessary, but usually single quotes around the whole string works best in PS.
-            // Example: cmd /c 'mklink /J "dest" "src"'
-            
-            // However, our cmd string already has quotes. 
-            // Let's rely on PS parsing. 
-            // cmd /c mklink /J "D:\..." "D:\..."
-            // In PS script: cmd /c $cmd  -- wait, $cmd needs to be exact.
-            
-            // outputting: cmd /c "mklink /J \"dest\" \"src\""
-            
-            // Simplest way: Write exact command line.
-            // cmd /c $cmd
-            
-            ps1_content.push_str(&format!("cmd /c '{}'\n", cmd));
-        }
-        
-        // ps1_content.push_str("Write-Host 'Press Key to exit...'\n");
-        // ps1_content.push_str("$null = $Host.UI.RawUI.ReadKey('NoEcho,IncludeKeyDown')\n");
-        
-        let temp_dir = std::env::temp_dir();
-        let ps1_path = temp_dir.join("git_worktree_links.ps1");
-        
-        if std::fs::write(&ps1_path, ps1_content).is_ok() {
-            let ps1_path_str = ps1_path.display().to_string();
-            
-            // Run PowerShell as Admin, executing the generated script SILENTLY
-            // 1. We use create_command("powershell") which creates a hidden process for the "launcher" shell.
-            // 2. We use Start-Process with -WindowStyle Hidden so the ELEVATED shell is also hidden.
-            let _ = create_command("powershell")
-                .arg("-Command")
-                .arg(format!("Start-Process powershell -Verb RunAs -WindowStyle Hidden -ArgumentList '-ExecutionPolicy Bypass -NoProfile -WindowStyle Hidden -File \"{}\"' -Wait", ps1_path_str))
-                .output();
+    let _ = git::Git::new(project_path)
+        .args(["worktree", "remove", "--force", path])
+        .timeout(git::CHECKOUT_TIMEOUT)
+        .run_blocking();
+    if dir.exists() {
+        if let Err(e) = std::fs::remove_dir_all(dir) {
+            println!("Rollback failed to delete {}: {}", path, e);
         }
     }
+    let _ = run_git(project_path, &["worktree", "prune"]);
+    if let Some(branch) = created_branch {
+        let _ = run_git(project_path, &["branch", "-D", branch]);
+    }
 }
 
+// Removes the worktree off the UI thread, reporting progress under `operation_id`.
+// It can be cancelled until the first file is deleted; after that it runs to the end.
 #[tauri::command]
-fn create_worktree(project_path: String, path: String, branch: String, base: Option<String>, smart_sync: bool) -> Result<(), String> {
-    let mut cmd = create_command("git");
-    cmd.current_dir(&project_path)
-       .arg("worktree")
-       .arg("add")
-       .arg("-b")
-       .arg(&branch)
-       .arg(&path);
-    
-    if let Some(b) = base {
-        cmd.arg(b);
+#[allow(clippy::too_many_arguments)]
+async fn remove_worktree(
+    app: tauri::AppHandle,
+    operations: State<'_, progress::Operations>,
+    project_path: String,
+    worktree_path: String,
+    branch: Option<String>,
+    base: Option<String>,
+    force: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<(), AppError> {
+    let op = operations.begin(app, operation_id);
+    run_blocking(move || {
+        delete_worktree(&op, &project_path, &worktree_path, branch, base, force.unwrap_or(false))
+    })
+    .await
+}
+
+fn delete_worktree(op: &progress::Operation, project_path: &str, worktree_path: &str, branch: Option<String>, base: Option<String>, force: bool) -> Result<(), AppError> {
+    // 0. Refuse to lose work unless the caller explicitly overrides
+    op.report(progress::Phase::Check, 0, None, Some(worktree_path));
+    let report = safety::build_report(project_path, worktree_path, branch.as_deref(), base.as_deref())?;
+    if !report.is_safe && !force {
+        return Err(AppError::UnsafeRemoval { summary: report.summary() });
     }
-    
-    let output = cmd.output().map_err(|e| e.to_string())?;
-    
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    op.check()?;
+
+    // Keep the branch tip and a snapshot of uncommitted changes so the removal can be undone
+    op.report(progress::Phase::Snapshot, 0, None, Some(worktree_path));
+    let trash_entry = trash::record_removal(project_path, worktree_path, branch.as_deref())?;
+    if op.is_cancelled() {
+        if let Some(entry) = &trash_entry {
+            trash::discard(project_path, entry)?;
+        }
+        return Err(AppError::Cancelled);
     }
 
-    // Auto-link gitignored files if enabled
-    if smart_sync {
-        link_gitignored_items(&project_path, &path);
+    // Take out Smart Sync links first so no recursive delete below can follow them
+    // into the main checkout (e.g. its node_modules or .env)
+    let mut unlinked = Vec::new();
+    if std::path::Path::new(worktree_path).exists() {
+        op.report(progress::Phase::Unlink, 0, None, Some(worktree_path));
+        unlinked = match smart_sync::unlink_before_removal(worktree_path) {
+            Ok(unlinked) => unlinked,
+            Err(e) => {
+                if let Some(entry) = &trash_entry {
+                    trash::discard(project_path, entry)?;
+                }
+                return Err(e);
+            }
+        };
     }
 
-    Ok(())
-}
-
-#[tauri::command]
-fn remove_worktree(project_path: String, worktree_path: String, branch: Option<String>) -> Result<(), String> {
     // 1. Remove Worktree
-    // We use --force, but if it fails (e.g. locked files or strange junctions), we manual clean.
-    let output = create_command("git")
-        .current_dir(&project_path)
+    // Only a forced removal uses --force; a clean worktree needs no override.
+    op.report(progress::Phase::Remove, 0, None, Some(worktree_path));
+    let mut cmd = git::Git::new(project_path)
         .arg("worktree")
-        .arg("remove")
-        .arg("--force")
-        .arg(&worktree_path)
-        .output()
-        .map_err(|e| e.to_string())?;
+        .arg("remove");
+    if force {
+        cmd = cmd.arg("--force");
+    }
+    let output = cmd
+        .arg(worktree_path)
+        .timeout(git::CHECKOUT_TIMEOUT)
+        .output_blocking()?;
+
+    // Without force, a git failure means something changed since the report; stop
+    // here and leave the worktree as it was, links and all.
+    if !force && !output.status.success() && std::path::Path::new(worktree_path).exists() {
+        if let Err(e) = smart_sync::relink(worktree_path, &unlinked) {
+            println!("Failed to restore the Smart Sync links of {}: {}", worktree_path, e);
+        }
+        if let Some(entry) = &trash_entry {
+            trash::discard(project_path, entry)?;
+        }
+        return Err(AppError::from_git(std::path::Path::new(project_path), git::failure(&output)));
+    }
 
     // Even if git fails (e.g. "not empty"), we try to force remove the directory manually
     // because we know we created junctions that git might choke on.
-    let path_obj = std::path::Path::new(&worktree_path);
+    let path_obj = std::path::Path::new(worktree_path);
     if path_obj.exists() {
         println!("Git remove finished (success={}), but dir exists. Force removing: {}", output.status.success(), worktree_path);
-        // std::fs::remove_dir_all is safe for symlinks/junctions (does not follow)
+        // Links were already removed by unlink_before_removal, so this only deletes the worktree's own files
         if let Err(e) = std::fs::remove_dir_all(path_obj) {
             println!("Failed to force remove directory: {}", e);
             // If git failed AND we failed to delete, then return error.
             if !output.status.success() {
-                 return Err(format!("Git failed: {}; Force remove failed: {}", String::from_utf8_lossy(&output.stderr), e));
+                 return Err(AppError::io(format!("Git failed ({}) and force remove failed", git::failure(&output).stderr), e));
             }
         }
     } else if !output.status.success() {
          // Dir gone but git reported error?
-         return Err(String::from_utf8_lossy(&output.stderr).to_string());
+         return Err(AppError::from_git(std::path::Path::new(project_path), git::failure(&output)));
     }
 
     // Run prune to clean up any stale git metadata if git command failed but we deleted dir
-    let _ = create_command("git").current_dir(&project_path).arg("worktree").arg("prune").output();
+    let _ = run_git(project_path, &["worktree", "prune"]);
 
     // 2. Delete Branch if provided
     if let Some(branch_name) = branch {
          // Only attempt delete if branch is valid
          if !branch_name.is_empty() {
-             let branch_output = create_command("git")
-                .current_dir(&project_path)
+             op.report(progress::Phase::Branch, 0, None, Some(&branch_name));
+             // The report already checked the branch against the base and its upstream,
+             // which `-d` does not know about. Only an overridden report leaves it to `-d`,
+             // so forcing past uncommitted changes never drops unmerged commits too.
+             let branch_output = git::Git::new(project_path)
                 .arg("branch")
-                .arg("-D")
+                .arg(if report.is_safe { "-D" } else { "-d" })
                 .arg(&branch_name)
-                .output()
-                .map_err(|e| e.to_string())?;
+                .output_blocking()?;
                 
              if !branch_output.status.success() {
-                 return Err(format!("Worktree removed, but failed to delete branch '{}': {}", branch_name, String::from_utf8_lossy(&branch_output.stderr)));
+                 // The worktree is gone at this point; only the branch is left
+                 return Err(AppError::from_git(std::path::Path::new(project_path), git::failure(&branch_output)));
              }
          }
     }
@@ -369,7 +339,7 @@ fn remove_worktree(project_path: String, worktree_path: String, branch: Option<S
 }
 
 #[tauri::command]
-fn open_terminal(path: String) -> Result<(), String> {
+fn open_terminal(path: String) -> Result<(), AppError> {
     #[cfg(target_os = "windows")]
     {
         // Use "start powershell -NoExit -WorkingDirectory 'path'" to open a new independent window
@@ -378,21 +348,22 @@ fn open_terminal(path: String) -> Result<(), String> {
             .args(["/c", "start", "powershell", "-NoExit", "-ExecutionPolicy", "Bypass", "-WorkingDirectory", &path])
             .creation_flags(0x08000000) // CREATE_NO_WINDOW for the cmd wrapper itself (optional, but good practice)
             .status()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| AppError::io("Failed to launch terminal", e))?;
 
         if !status.success() {
-            return Err("Failed to launch terminal".to_string());
+            return Err(AppError::Other("Failed to launch terminal".to_string()));
         }
+        Ok(())
     }
     #[cfg(not(target_os = "windows"))]
     {
-        return Err("Open Terminal is currently Windows only".to_string());
+        let _ = path;
+        Err(AppError::Unsupported("Open Terminal".to_string()))
     }
-    Ok(())
 }
 
 #[tauri::command]
-fn open_worktree_dir(path: String) -> Result<(), String> {
+fn open_worktree_dir(path: String) -> Result<(), AppError> {
     println!("Attempting to open path: '{}'", path);
     // Try cleaning up the path for Windows
     #[cfg(target_os = "windows")]
@@ -402,158 +373,70 @@ fn open_worktree_dir(path: String) -> Result<(), String> {
 
     opener::open(&path).map_err(|e| {
         println!("Opener error: {}", e);
-        format!("Failed to open folder '{}': {}", path, e)
+        AppError::Other(format!("Failed to open folder '{}': {}", path, e))
     })?;
     Ok(())
 }
 
-async fn hook_handler(
-    AxumState(state): AxumState<Arc<ServerState>>,
-    Json(payload): Json<HookPayload>,
-) {
-    println!("=== Hook Received ===");
-    println!("Path: '{}'", payload.path);
-    println!("Status: {}", payload.status);
-    println!("====================");
-    
-    use tauri::Emitter;
-    let _ = state.app_handle.emit("claude-status-change", &payload);
-    
-    if payload.status == "waiting_auth" {
-         let _ = state.app_handle.notification()
-            .builder()
-            .title("Claude Permission Request")
-            .body("Claude is waiting for your approval.")
-            .show();
+// The gwm-hook helper, installed next to the app's executable.
+fn hook_binary() -> Result<std::path::PathBuf, AppError> {
+    let exe = std::env::current_exe().map_err(|e| AppError::HookInstall(e.to_string()))?;
+    let hook = exe.with_file_name(if cfg!(target_os = "windows") { "gwm-hook.exe" } else { "gwm-hook" });
+    if !hook.exists() {
+        return Err(AppError::HookInstall(format!("'{}' is missing; reinstall the app", hook.display())));
     }
+    Ok(hook)
 }
 
+// Registers gwm-hook in ~/.claude/settings.json (see claude_settings.rs).
 #[tauri::command]
-fn install_claude_hooks() -> Result<(), String> {
-    #[cfg(target_os = "windows")]
-    {
-        let user_profile = std::env::var("USERPROFILE").map_err(|_| "Could not find USERPROFILE")?;
-        let claude_dir = std::path::Path::new(&user_profile).join(".claude");
-        let settings_path = claude_dir.join("settings.json");
-        let hooks_dir = claude_dir.join("hooks");
-        
-        if !claude_dir.exists() {
-             std::fs::create_dir_all(&claude_dir).map_err(|e| e.to_string())?;
-        }
-        if !hooks_dir.exists() {
-             std::fs::create_dir_all(&hooks_dir).map_err(|e| e.to_string())?;
-        }
-        
-        // 1. Write Hook Script
-        let hook_script_path = hooks_dir.join("git-worktree-hook.ps1");
-        let script_content = r#"
-param (
-    [string]$Type
-)
-
-$Path = Get-Location
-$Payload = @{
-    path = $Path.Path
-    status = "idle"
-    message = ""
-}
+fn install_claude_hooks() -> Result<(), AppError> {
+    let claude_dir = hook_server::claude_dir()?;
+    let hooks_dir = claude_dir.join("hooks");
 
-switch ($Type) {
-    "PermissionRequest" { $Payload.status = "waiting_auth" }
-    "PreToolUse" { $Payload.status = "running" }
-    "PostToolUse" { $Payload.status = "running" }
-    "Stop" { $Payload.status = "idle" }
-}
+    if !hooks_dir.exists() {
+         std::fs::create_dir_all(&hooks_dir).map_err(|e| AppError::HookInstall(e.to_string()))?;
+    }
 
+    // Replaced by gwm-hook; earlier versions installed this script
+    let _ = std::fs::remove_file(hooks_dir.join("git-worktree-hook.ps1"));
 
-try {
-    $jsonBody = $Payload | ConvertTo-Json -Compress
-    $utf8Bytes = [System.Text.Encoding]::UTF8.GetBytes($jsonBody)
-    Invoke-RestMethod -Uri "http://localhost:36911/claude/status" -Method Post -Body $utf8Bytes -ContentType "application/json; charset=utf-8" -ErrorAction SilentlyContinue
-} catch {}
-"#;
-        // Write with UTF-8 BOM to ensure PowerShell interprets it correctly
-        let bom = b"\xEF\xBB\xBF";
-        let mut content_with_bom = Vec::new();
-        content_with_bom.extend_from_slice(bom);
-        content_with_bom.extend_from_slice(script_content.as_bytes());
-        std::fs::write(&hook_script_path, content_with_bom).map_err(|e| e.to_string())?;
-        
-        // 2. Update settings.json
-        // This is tricky without a JSON parser crate helper, but I imported serde_json
-        // I need to read existing or create new.
-        let mut settings: serde_json::Value = if settings_path.exists() {
-            let content = std::fs::read_to_string(&settings_path).map_err(|e| e.to_string())?;
-            serde_json::from_str(&content).unwrap_or(serde_json::json!({}))
-        } else {
-             serde_json::json!({})
-        };
-        
-        if settings.get("hooks").is_none() {
-            settings["hooks"] = serde_json::json!({});
-        }
-        
-        // Use ampersand execution operator which handles quoted paths better in some contexts
-        // Also stick to backslashes but escape them for JSON string
-        let path_str = hook_script_path.to_string_lossy().to_string();
-        let cmd_base = format!("powershell -ExecutionPolicy Bypass -Command \"& '{}' -Type\"", path_str);
-        
-        // Helper to create the new hook structure: [{ "hooks": [{ "type": "command", "command": "..." }] }]
-        // We omit "matcher" to apply to all events of that type
-        let make_hook = |event_type: &str| {
-            serde_json::json!([
-                {
-                    "hooks": [
-                        {
-                            "type": "command",
-                            "command": format!("{} '{}'\"", cmd_base, event_type)
-                        }
-                    ]
-                }
-            ])
-        };
+    // Claude runs hooks through a shell; quote the path, it may contain spaces.
+    // The hook server turns away posts without the secret (see hook_server.rs)
+    let hook = hook_binary()?;
+    let secret = hook_server::hook_secret()?;
+    let cmd_base = format!("\"{}\" --token {}", hook.display(), secret);
 
-        settings["hooks"]["PermissionRequest"] = make_hook("PermissionRequest");
-        settings["hooks"]["PreToolUse"] = make_hook("PreToolUse");
-        settings["hooks"]["PostToolUse"] = make_hook("PostToolUse");
-        settings["hooks"]["Stop"] = make_hook("Stop");
-        
-        let new_content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
-        std::fs::write(&settings_path, new_content).map_err(|e| e.to_string())?;
-    }
+    claude_settings::install_hooks(&claude_dir.join("settings.json"), &cmd_base)?;
     Ok(())
 }
 
 struct ClaudeState(Mutex<HashMap<String, u32>>);
 
 #[tauri::command]
-fn open_claude(path: String, state: State<'_, ClaudeState>) -> Result<(), String> {
+fn open_claude(path: String, state: State<'_, ClaudeState>) -> Result<(), AppError> {
     println!("Opening Claude in: {}", path);
     
     // Check if already running (basic check)
-    let mut session_map = state.0.lock().map_err(|_| "Failed to lock state")?;
-    
-    // Spawn PowerShell with Start-Process to ensure new window
-    // We use -PassThru to get process info back, and Select-Object -ExpandProperty Id to get the PID
-    #[cfg(target_os = "windows")]
-    let output = create_command("powershell")
-        .arg("-ExecutionPolicy")
-        .arg("Bypass")
-        .arg("-Command")
-        .arg(format!(
-            "Start-Process powershell -ArgumentList '-NoExit', '-Command', \"Set-Location -LiteralPath '{}'; claude\" -PassThru | Select-Object -ExpandProperty Id", 
-            path
-        ))
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    #[cfg(not(target_os = "windows"))]
-    return Err("Claude integration currently only supports Windows".to_string());
+    let mut session_map = state.0.lock().map_err(|_| AppError::Session("Failed to lock state".to_string()))?;
     
     #[cfg(target_os = "windows")]
     {
+        // Spawn PowerShell with Start-Process to ensure new window
+        // We use -PassThru to get process info back, and Select-Object -ExpandProperty Id to get the PID
+        let output = create_command("powershell")
+            .arg("-ExecutionPolicy")
+            .arg("Bypass")
+            .arg("-Command")
+            .arg(format!(
+                "Start-Process powershell -ArgumentList '-NoExit', '-Command', \"Set-Location -LiteralPath '{}'; claude\" -PassThru | Select-Object -ExpandProperty Id", 
+                path
+            ))
+            .output()
+            .map_err(|e| AppError::Session(e.to_string()))?;
+
         if !output.status.success() {
-             return Err(format!("Failed to spawn process: {}", String::from_utf8_lossy(&output.stderr)));
+             return Err(AppError::Session(format!("Failed to spawn process: {}", String::from_utf8_lossy(&output.stderr))));
         }
         
         // Parse PID from stdout
@@ -564,55 +447,56 @@ fn open_claude(path: String, state: State<'_, ClaudeState>) -> Result<(), String
         if let Ok(pid) = pid_str.parse::<u32>() {
             session_map.insert(path, pid);
         } else {
-             return Err(format!("Failed to parse PID from '{}'", pid_str));
+             return Err(AppError::Session(format!("Failed to parse PID from '{}'", pid_str)));
         }
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = &mut session_map;
+        Err(AppError::Unsupported("Claude integration".to_string()))
     }
-    
-    Ok(())
 }
 
 #[tauri::command]
-fn focus_claude(path: String, state: State<'_, ClaudeState>) -> Result<(), String> {
-    let mut session_map = state.0.lock().map_err(|_| "Failed to lock state")?;
+fn focus_claude(path: String, state: State<'_, ClaudeState>) -> Result<(), AppError> {
+    let session_map = state.0.lock().map_err(|_| AppError::Session("Failed to lock state".to_string()))?;
     
-    if let Some(&pid) = session_map.get(&path) {
-         println!("Focusing PID: {}", pid);
-         let script = format!("(New-Object -ComObject WScript.Shell).AppActivate({})", pid);
-         #[cfg(target_os = "windows")]
-         let output = create_command("powershell")
+    let Some(&pid) = session_map.get(&path) else {
+        return Err(AppError::NoSession { path });
+    };
+    println!("Focusing PID: {}", pid);
+
+    #[cfg(target_os = "windows")]
+    {
+        let script = format!("(New-Object -ComObject WScript.Shell).AppActivate({})", pid);
+        // AppActivate prints False when the window is already active or focus is refused.
+        // Not an error: the session poll drops it if the process is actually gone.
+        create_command("powershell")
             .arg("-ExecutionPolicy")
             .arg("Bypass")
             .arg("-Command")
             .arg(&script)
             .output()
-            .map_err(|e| e.to_string())?;
-
-         #[cfg(not(target_os = "windows"))]
-         return Err("Not supported".to_string());
-            
-         let out_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
-         println!("Focus result for PID {}: '{}'", pid, out_str);
-         
-         if out_str == "False" {
-             println!("Focus returned False (window might be already active or prevented). Treating as success to avoid UI error since user reports it works.");
-             // Do not return Err, just Ok. Polling will clean up if it's actually dead.
-             return Ok(());
-         }
-         Ok(())
-    } else {
-        Err("No active Claude session found for this path".to_string())
+            .map_err(|e| AppError::Session(e.to_string()))?;
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err(AppError::Unsupported("Focusing Claude".to_string()))
     }
 }
 
 #[tauri::command]
-fn list_claude_sessions(state: State<'_, ClaudeState>) -> Result<Vec<String>, String> {
-    let mut session_map = state.0.lock().map_err(|_| "Failed to lock state")?;
+fn list_claude_sessions(state: State<'_, ClaudeState>) -> Result<Vec<String>, AppError> {
+    let mut session_map = state.0.lock().map_err(|_| AppError::Session("Failed to lock state".to_string()))?;
     
     // Cleanup dead sessions logic could go here
     // iterate and check if PID is alive.
     // For Windows, "tasklist /FI 'PID eq <pid>'" is one way, or just Get-Process
     
-    let mut dead_paths = Vec::new();
+    #[allow(unused_mut)]
+    let mut dead_paths: Vec<String> = Vec::new();
     
     for (path, &pid) in session_map.iter() {
          #[cfg(target_os = "windows")]
@@ -639,6 +523,9 @@ fn list_claude_sessions(state: State<'_, ClaudeState>) -> Result<Vec<String>, St
                  }
              }
          }
+         // Sessions are only recorded on Windows (see open_claude)
+         #[cfg(not(target_os = "windows"))]
+         let _ = (path, pid);
     }
     
     for p in dead_paths {
@@ -650,8 +537,8 @@ fn list_claude_sessions(state: State<'_, ClaudeState>) -> Result<Vec<String>, St
 }
 
 #[tauri::command]
-fn kill_claude_session(path: String, state: State<'_, ClaudeState>) -> Result<(), String> {
-    let mut session_map = state.0.lock().map_err(|_| "Failed to lock state")?;
+fn kill_claude_session(path: String, state: State<'_, ClaudeState>) -> Result<(), AppError> {
+    let mut session_map = state.0.lock().map_err(|_| AppError::Session("Failed to lock state".to_string()))?;
     
     if let Some(&pid) = session_map.get(&path) {
          println!("Killing session for path: {} (PID: {})", path, pid);
@@ -665,7 +552,7 @@ fn kill_claude_session(path: String, state: State<'_, ClaudeState>) -> Result<()
                 .arg("/PID")
                 .arg(pid.to_string())
                 .output()
-                .map_err(|e| e.to_string())?;
+                .map_err(|e| AppError::Session(e.to_string()))?;
                 
              if !output.status.success() {
                  let stderr = String::from_utf8_lossy(&output.stderr);
@@ -688,7 +575,10 @@ pub fn run() {
     .plugin(tauri_plugin_dialog::init())
     .plugin(tauri_plugin_notification::init())
     .manage(ClaudeState(Mutex::new(HashMap::new())))
-    .invoke_handler(tauri::generate_handler![list_worktrees, create_worktree, remove_worktree, open_worktree_dir, open_claude, focus_claude, list_claude_sessions, kill_claude_session, install_claude_hooks, open_terminal])
+    .manage(progress::Operations::default())
+    .manage(watcher::Watchers::default())
+    .manage(hook_server::HookServer::default())
+    .invoke_handler(tauri::generate_handler![list_worktrees, list_branches, status::worktree_status, safety::removal_report, trash::list_trash, trash::restore_worktree, trash::purge_trash, smart_sync::get_smart_sync_config, smart_sync::set_smart_sync_config, smart_sync::get_sync_report, smart_sync::plan_smart_sync, smart_sync::resync_worktree, smart_sync::materialize_item, smart_sync::sync_source_candidates, progress::cancel_operation, watcher::watch_worktrees, create_worktree, remove_worktree, open_worktree_dir, open_claude, focus_claude, list_claude_sessions, kill_claude_session, install_claude_hooks, hook_server::hook_server_status, open_terminal])
     .setup(|app| {
       if cfg!(debug_assertions) {
         app.handle().plugin(
@@ -699,23 +589,8 @@ pub fn run() {
       }
 
       // Start local Hook Server
-      let app_handle = app.handle().clone();
-      tauri::async_runtime::spawn(async move {
-          let state = Arc::new(ServerState { app_handle });
-          
-          let router = Router::new()
-              .route("/claude/status", post(hook_handler))
-              .with_state(state);
-              
-          // Silent unwrap for now, assumes port 36911 is free.
-          if let Ok(listener) = tokio::net::TcpListener::bind("127.0.0.1:36911").await {
-              println!("Hook server running on 36911");
-              let _ = axum::serve(listener, router).await;
-          } else {
-              eprintln!("Failed to bind port 36911 for Hook Server");
-          }
-      });
-      
+      hook_server::start(app.handle().clone());
+
       Ok(())
     })
     .run(tauri::generate_context!())
@@ -0,0 +1,233 @@
+use git2::{BranchType, ErrorClass, ErrorCode, ObjectType, Repository, Status, StatusOptions, WorktreeLockStatus};
+use std::path::Path;
+
+use crate::backend::{short_branch_name, Branch, DiffStats, GitBackend, RefInfo};
+use crate::error::{AppError, GitFailure};
+use crate::status::{AheadBehind, CommitInfo, WorktreeStatus};
+use crate::Worktree;
+
+// What `git worktree list` shows for a HEAD that has no commit yet
+const NULL_OID: &str = "0000000000000000000000000000000000000000";
+
+// Answers in-process with libgit2, without starting a git process per query.
+// Mirrors what the git CLI prints, down to paths and prune reasons.
+pub struct Libgit2Backend;
+
+// Sorts a libgit2 error the way AppError::from_git sorts git's stderr.
+fn error(dir: &Path, e: git2::Error) -> AppError {
+    let git = GitFailure { exit_code: None, stderr: e.message().to_string() };
+    match (e.code(), e.class()) {
+        (ErrorCode::NotFound, ErrorClass::Repository) => AppError::NotARepository { path: dir.display().to_string(), git },
+        (ErrorCode::NotFound, ErrorClass::Reference | ErrorClass::Odb | ErrorClass::Object)
+        | (ErrorCode::InvalidSpec | ErrorCode::Ambiguous, _) => AppError::InvalidRef { git },
+        (ErrorCode::Locked, ErrorClass::Worktree) => AppError::WorktreeLocked { git },
+        _ => AppError::Git { git },
+    }
+}
+
+fn open(dir: &Path) -> Result<Repository, AppError> {
+    Repository::discover(dir).map_err(|e| error(dir, e))
+}
+
+// Resolves the contents of a HEAD file ("ref: refs/heads/x" or a hash) against
+// the repository's refs: (hash, branch). The hash is all zeros while the branch is unborn.
+fn read_head(repo: &Repository, head_file: &Path) -> (String, Option<String>) {
+    let content = std::fs::read_to_string(head_file).unwrap_or_default();
+    let content = content.trim();
+    match content.strip_prefix("ref: ") {
+        Some(name) => {
+            let hash = repo.refname_to_id(name).map(|id| id.to_string()).unwrap_or_else(|_| NULL_OID.to_string());
+            (hash, Some(name.strip_prefix("refs/heads/").unwrap_or(name).to_string()))
+        }
+        None => (content.to_string(), None),
+    }
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().trim_end_matches(['/', '\\']).to_string()
+}
+
+impl GitBackend for Libgit2Backend {
+    fn name(&self) -> &'static str {
+        "libgit2"
+    }
+
+    fn list_worktrees(&self, repo: &Path) -> Result<Vec<Worktree>, AppError> {
+        let discovered = open(repo)?;
+        let common_dir = discovered.commondir().to_path_buf();
+        let main = if discovered.is_worktree() {
+            Repository::open(&common_dir).map_err(|e| error(repo, e))?
+        } else {
+            discovered
+        };
+
+        // git names the main worktree after its (resolved) common dir
+        let common = path_string(&common_dir);
+        let mut main_worktree = Worktree {
+            path: common.strip_suffix("/.git").unwrap_or(&common).to_string(),
+            is_bare: main.is_bare(),
+            ..Default::default()
+        };
+        if !main.is_bare() {
+            let (hash, branch) = read_head(&main, &common_dir.join("HEAD"));
+            main_worktree.is_detached = branch.is_none();
+            main_worktree.head_hash = hash;
+            main_worktree.branch = branch;
+        }
+
+        let mut linked = Vec::new();
+        let names = main.worktrees().map_err(|e| error(repo, e))?;
+        for name in names.iter().flatten() {
+            let Ok(wt) = main.find_worktree(name) else {
+                continue;
+            };
+            let (hash, branch) = read_head(&main, &common_dir.join("worktrees").join(name).join("HEAD"));
+            let lock_reason = match wt.is_locked() {
+                Ok(WorktreeLockStatus::Locked(reason)) => Some(reason.map(|r| r.trim().to_string()).filter(|r| !r.is_empty())),
+                _ => None,
+            };
+            // Same rule as `git worktree list`: a linked worktree whose checkout is gone can be pruned, unless locked
+            let is_prunable = lock_reason.is_none() && !wt.path().join(".git").exists();
+            linked.push(Worktree {
+                path: path_string(wt.path()),
+                head_hash: hash,
+                is_detached: branch.is_none(),
+                branch,
+                is_bare: false,
+                is_locked: lock_reason.is_some(),
+                lock_reason: lock_reason.flatten(),
+                is_prunable,
+                prunable_reason: is_prunable.then(|| "gitdir file points to non-existent location".to_string()),
+            });
+        }
+        linked.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut worktrees = vec![main_worktree];
+        worktrees.extend(linked);
+        Ok(worktrees)
+    }
+
+    fn status(&self, worktree: &Path, base: Option<&str>) -> Result<WorktreeStatus, AppError> {
+        let repo = open(worktree)?;
+        let mut status = WorktreeStatus {
+            path: worktree.to_string_lossy().to_string(),
+            base: base.map(|b| b.to_string()),
+            ..Default::default()
+        };
+
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(false)
+            .include_ignored(false)
+            .renames_head_to_index(true);
+        let statuses = repo.statuses(Some(&mut options)).map_err(|e| error(worktree, e))?;
+        let staged = Status::INDEX_NEW | Status::INDEX_MODIFIED | Status::INDEX_DELETED | Status::INDEX_RENAMED | Status::INDEX_TYPECHANGE;
+        let unstaged = Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE;
+        for entry in statuses.iter() {
+            let s = entry.status();
+            if s.contains(Status::CONFLICTED) {
+                status.conflicted += 1;
+                continue;
+            }
+            if s.intersects(staged) {
+                status.staged += 1;
+            }
+            if s.intersects(unstaged) {
+                status.unstaged += 1;
+            }
+            if s.contains(Status::WT_NEW) {
+                status.untracked += 1;
+            }
+        }
+
+        let head = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+
+        // Upstream of the checked out branch, shown even when its ref is gone
+        let head_ref = repo.find_reference("HEAD").ok().and_then(|h| h.symbolic_target().map(String::from));
+        if let Some(upstream) = head_ref.and_then(|r| repo.branch_upstream_name(&r).ok()) {
+            let upstream = upstream.as_str().unwrap_or_default().to_string();
+            status.upstream = Some(short_branch_name(&upstream).to_string());
+            if let (Some(head), Ok(target)) = (&head, repo.refname_to_id(&upstream)) {
+                if let Ok((ahead, behind)) = repo.graph_ahead_behind(head.id(), target) {
+                    status.upstream_ahead_behind = Some(AheadBehind { ahead: ahead as u32, behind: behind as u32 });
+                }
+            }
+        }
+
+        if let (Some(base), Some(head)) = (base, &head) {
+            let base = repo.revparse_single(base).and_then(|o| o.peel_to_commit());
+            if let Ok(base) = base {
+                if let Ok((ahead, behind)) = repo.graph_ahead_behind(head.id(), base.id()) {
+                    status.base_ahead_behind = Some(AheadBehind { ahead: ahead as u32, behind: behind as u32 });
+                }
+            }
+        }
+
+        status.last_commit = head.map(|commit| CommitInfo {
+            hash: commit.id().to_string(),
+            subject: commit.summary().unwrap_or_default().to_string(),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            timestamp: commit.time().seconds(),
+        });
+        Ok(status)
+    }
+
+    fn branches(&self, repo: &Path) -> Result<Vec<Branch>, AppError> {
+        let git = open(repo)?;
+        let mut branches = Vec::new();
+        for branch in git.branches(None).map_err(|e| error(repo, e))? {
+            let (branch, kind) = branch.map_err(|e| error(repo, e))?;
+            let reference = branch.get();
+            // Symbolic refs such as refs/remotes/origin/HEAD are not branches of their own
+            let (Some(name), Some(target)) = (reference.name(), reference.target()) else {
+                continue;
+            };
+            let upstream = match kind {
+                BranchType::Local => git.branch_upstream_name(name).ok().and_then(|u| u.as_str().map(|u| short_branch_name(u).to_string())),
+                BranchType::Remote => None,
+            };
+            branches.push(Branch {
+                name: short_branch_name(name).to_string(),
+                is_remote: kind == BranchType::Remote,
+                target: target.to_string(),
+                upstream,
+            });
+        }
+        branches.sort_by(|a, b| (a.is_remote, &a.name).cmp(&(b.is_remote, &b.name)));
+        Ok(branches)
+    }
+
+    fn refs(&self, repo: &Path) -> Result<Vec<RefInfo>, AppError> {
+        let git = open(repo)?;
+        let mut refs = Vec::new();
+        for reference in git.references().map_err(|e| error(repo, e))? {
+            let reference = reference.map_err(|e| error(repo, e))?;
+            let (Some(name), Ok(resolved)) = (reference.name(), reference.resolve()) else {
+                continue;
+            };
+            if let Some(target) = resolved.target() {
+                refs.push(RefInfo { name: name.to_string(), target: target.to_string() });
+            }
+        }
+        refs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(refs)
+    }
+
+    fn diff_stats(&self, worktree: &Path, against: Option<&str>) -> Result<DiffStats, AppError> {
+        let repo = open(worktree)?;
+        let tree = repo
+            .revparse_single(against.unwrap_or("HEAD"))
+            .and_then(|o| o.peel(ObjectType::Tree))
+            .map_err(|e| error(worktree, e))?;
+        let diff = repo
+            .diff_tree_to_workdir_with_index(tree.as_tree(), None)
+            .map_err(|e| error(worktree, e))?;
+        let stats = diff.stats().map_err(|e| error(worktree, e))?;
+        Ok(DiffStats {
+            files_changed: stats.files_changed() as u32,
+            insertions: stats.insertions() as u32,
+            deletions: stats.deletions() as u32,
+        })
+    }
+}
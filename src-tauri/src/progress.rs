@@ -0,0 +1,121 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::error::AppError;
+use crate::walk::CancelFlag;
+
+const PROGRESS_EVENT: &str = "operation-progress";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Checkout,
+    Discover,
+    Sync,
+    Size,
+    Check,
+    Snapshot,
+    Unlink,
+    Remove,
+    Branch,
+    RollBack,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub operation_id: String,
+    pub phase: Phase,
+    // Items handled so far in this phase, out of `total` when known
+    pub count: usize,
+    pub total: Option<usize>,
+    pub current_path: Option<String>,
+}
+
+// Cancellation flags of the operations currently running, by id.
+#[derive(Default)]
+pub struct Operations(Arc<Mutex<HashMap<String, CancelFlag>>>);
+
+impl Operations {
+    // Registers an operation the UI can cancel under `id` (when it gave one).
+    pub fn begin(&self, app: AppHandle, id: Option<String>) -> Operation {
+        let mut op = Operation::untracked(app);
+        if let Some(id) = id.filter(|id| !id.is_empty()) {
+            self.0.lock().unwrap().insert(id.clone(), op.cancel.clone());
+            op.id = id;
+            op.registry = Some(self.0.clone());
+        }
+        op
+    }
+}
+
+// A long-running command: reports progress and knows when it has been cancelled.
+pub struct Operation {
+    app: AppHandle,
+    id: String,
+    cancel: CancelFlag,
+    registry: Option<Arc<Mutex<HashMap<String, CancelFlag>>>>,
+}
+
+impl Operation {
+    // Reports progress but cannot be cancelled from the UI.
+    pub fn untracked(app: AppHandle) -> Self {
+        Operation { app, id: String::new(), cancel: Arc::new(AtomicBool::new(false)), registry: None }
+    }
+
+    pub fn app(&self) -> &AppHandle {
+        &self.app
+    }
+
+    pub fn cancel_flag(&self) -> CancelFlag {
+        self.cancel.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    // Err(Cancelled) once the operation has been cancelled, for use with `?`.
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        Ok(())
+    }
+
+    pub fn report(&self, phase: Phase, count: usize, total: Option<usize>, current_path: Option<&str>) {
+        if self.id.is_empty() {
+            return;
+        }
+        let event = ProgressEvent {
+            operation_id: self.id.clone(),
+            phase,
+            count,
+            total,
+            current_path: current_path.map(String::from),
+        };
+        let _ = self.app.emit(PROGRESS_EVENT, event);
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        if let Some(registry) = &self.registry {
+            registry.lock().unwrap().remove(&self.id);
+        }
+    }
+}
+
+// Asks a running operation to stop. Returns false when no such operation is running.
+#[tauri::command]
+pub fn cancel_operation(operations: State<'_, Operations>, operation_id: String) -> bool {
+    match operations.0.lock().unwrap().get(&operation_id) {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::{run_blocking, run_git, smart_sync};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitSummary {
+    pub hash: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StashEntry {
+    pub name: String, // e.g. "stash@{0}"
+    pub message: String,
+}
+
+// What would be lost (or left behind) by removing a worktree and its branch.
+#[derive(Debug, Default, Serialize)]
+pub struct RemovalReport {
+    pub worktree_path: String,
+    pub branch: Option<String>,
+    // `git status --porcelain` entries ("XY path"), untracked files included
+    pub uncommitted_files: Vec<String>,
+    // Commits on the worktree's tip that neither the base nor the upstream contain
+    pub unmerged_commits: Vec<CommitSummary>,
+    // Stashes recorded on this branch. They survive removal, so they only warn.
+    pub stashes: Vec<StashEntry>,
+    // Other worktrees whose Smart Sync links point into this one; they would dangle. Only warns.
+    pub linked_from: Vec<String>,
+    pub is_safe: bool,
+}
+
+impl RemovalReport {
+    // One-line description for error messages
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.uncommitted_files.is_empty() {
+            parts.push(format!("{} uncommitted file(s)", self.uncommitted_files.len()));
+        }
+        if !self.unmerged_commits.is_empty() {
+            parts.push(format!("{} unmerged commit(s)", self.unmerged_commits.len()));
+        }
+        if !self.stashes.is_empty() {
+            parts.push(format!("{} stash(es)", self.stashes.len()));
+        }
+        if !self.linked_from.is_empty() {
+            parts.push(format!("linked from {} worktree(s)", self.linked_from.len()));
+        }
+        parts.join(", ")
+    }
+}
+
+fn uncommitted_files(worktree_path: &str) -> Result<Vec<String>, AppError> {
+    let output = run_git(worktree_path, &["status", "--porcelain", "-z"])?;
+    // Links Smart Sync made are not work to lose. They are excluded from git, but
+    // worktrees synced before that still list them as untracked.
+    let linked = smart_sync::linked_paths(worktree_path);
+    let mut files = Vec::new();
+    let mut fields = output.split('\0').filter(|f| !f.is_empty());
+
+    while let Some(entry) = fields.next() {
+        // Renames and copies are followed by their original path
+        if entry.starts_with('R') || entry.starts_with('C') {
+            fields.next();
+        }
+        let is_link = entry.strip_prefix("?? ").is_some_and(|path| linked.iter().any(|l| l == path.trim_end_matches('/')));
+        if !is_link {
+            files.push(entry.to_string());
+        }
+    }
+    Ok(files)
+}
+
+fn unmerged_commits(project_path: &str, tip: &str, base: Option<&str>, upstream: Option<&str>) -> Result<Vec<CommitSummary>, AppError> {
+    let mut args = vec!["log", "--format=%H%x00%s", tip, "--not"];
+    args.extend(base);
+    args.extend(upstream);
+
+    let output = run_git(project_path, &args)?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let (hash, subject) = line.split_once('\0')?;
+            Some(CommitSummary { hash: hash.to_string(), subject: subject.to_string() })
+        })
+        .collect())
+}
+
+fn branch_stashes(project_path: &str, branch: &str) -> Vec<StashEntry> {
+    // Stash subjects look like "WIP on <branch>: ..." or "On <branch>: ..."
+    let wip = format!("WIP on {}:", branch);
+    let on = format!("On {}:", branch);
+
+    run_git(project_path, &["stash", "list", "--format=%gd%x00%gs"])
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once('\0'))
+        .filter(|(_, message)| message.starts_with(&wip) || message.starts_with(&on))
+        .map(|(name, message)| StashEntry { name: name.to_string(), message: message.to_string() })
+        .collect()
+}
+
+pub fn build_report(project_path: &str, worktree_path: &str, branch: Option<&str>, base: Option<&str>) -> Result<RemovalReport, AppError> {
+    let branch = branch.filter(|b| !b.is_empty());
+    let mut report = RemovalReport {
+        worktree_path: worktree_path.to_string(),
+        branch: branch.map(|b| b.to_string()),
+        ..Default::default()
+    };
+
+    let worktree_exists = std::path::Path::new(worktree_path).exists();
+    if worktree_exists {
+        report.uncommitted_files = uncommitted_files(worktree_path)?;
+        report.linked_from = smart_sync::linked_from(project_path, worktree_path);
+    }
+
+    // The tip is the branch when we know it, otherwise whatever the (detached) worktree has checked out
+    let tip = match branch {
+        Some(b) => Some(format!("refs/heads/{}", b)),
+        None if worktree_exists => run_git(worktree_path, &["rev-parse", "HEAD"]).ok().map(|h| h.trim().to_string()),
+        None => None,
+    };
+
+    if let Some(tip) = tip {
+        let upstream = branch.and_then(|b| {
+            run_git(project_path, &["rev-parse", "--abbrev-ref", "--symbolic-full-name", &format!("{}@{{upstream}}", b)])
+                .ok()
+                .map(|u| u.trim().to_string())
+        });
+        // Without an explicit base, compare against whatever the main checkout has out
+        let base = match base.filter(|b| !b.is_empty()) {
+            Some(b) => Some(b.to_string()),
+            None => run_git(project_path, &["rev-parse", "HEAD"]).ok().map(|h| h.trim().to_string()),
+        };
+        report.unmerged_commits = unmerged_commits(project_path, &tip, base.as_deref(), upstream.as_deref())?;
+    }
+
+    if let Some(b) = branch {
+        report.stashes = branch_stashes(project_path, b);
+    }
+
+    report.is_safe = report.uncommitted_files.is_empty() && report.unmerged_commits.is_empty();
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn removal_report(project_path: String, worktree_path: String, branch: Option<String>, base: Option<String>) -> Result<RemovalReport, AppError> {
+    run_blocking(move || build_report(&project_path, &worktree_path, branch.as_deref(), base.as_deref())).await
+}
@@ -0,0 +1,1167 @@
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+
+#[cfg(target_os = "windows")]
+use crate::create_command;
+use crate::bootstrap::{self, Bootstrap, BootstrapReport, MismatchAction};
+use crate::error::AppError;
+use crate::progress::{Operation, Operations, Phase};
+use crate::walk::{self, CancelFlag, WalkOptions};
+use crate::{backend, manager_dir, run_blocking, run_git};
+
+// How an ignored item is brought into a worktree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStrategy {
+    // Shared with the source (junction for directories on Windows)
+    #[default]
+    Symlink,
+    // Files hard-linked; directories recreated with hard-linked files
+    Hardlink,
+    // Copy-on-write clone (FICLONE on btrfs/XFS, clonefile on APFS), copying where unsupported
+    Reflink,
+    // Independent copy each worktree can modify freely
+    Copy,
+    // Left out of the worktree entirely
+    Skip,
+}
+
+// Applies `strategy` to ignored items matching `pattern`.
+// Patterns follow gitignore conventions: without a '/' they match the item's
+// name at any depth, with one they match the path from the repo root, and a
+// trailing '/' restricts the rule to directories. The first matching rule wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRule {
+    pub pattern: String,
+    pub strategy: SyncStrategy,
+}
+
+// Per-repo Smart Sync settings, stored in the shared git dir.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartSyncConfig {
+    // Strategy for ignored files no rule matches (directories default to symlink)
+    pub file_mode: SyncStrategy,
+    pub rules: Vec<SyncRule>,
+    // What happens to dependency folders (node_modules, target) when the
+    // worktree's lockfile differs from the source's
+    pub lockfile_mismatch: MismatchAction,
+    // Overrides the install command picked from the lockfile (e.g. "pnpm install --offline")
+    pub install_command: Option<String>,
+}
+
+struct CompiledRule {
+    matcher: GlobMatcher,
+    dir_only: bool,
+    strategy: SyncStrategy,
+}
+
+struct RuleSet(Vec<CompiledRule>);
+
+impl RuleSet {
+    fn new(rules: &[SyncRule]) -> Result<Self, AppError> {
+        let mut compiled = Vec::new();
+        for rule in rules {
+            let pattern = rule.pattern.trim();
+            let dir_only = pattern.ends_with('/');
+            let pattern = pattern.trim_end_matches('/');
+            let glob = match pattern.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if pattern.contains('/') => pattern.to_string(),
+                None => format!("**/{}", pattern),
+            };
+            let matcher = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| AppError::InvalidPattern { pattern: rule.pattern.clone(), reason: e.kind().to_string() })?
+                .compile_matcher();
+            compiled.push(CompiledRule { matcher, dir_only, strategy: rule.strategy });
+        }
+        Ok(RuleSet(compiled))
+    }
+
+    fn strategy_for(&self, item: &IgnoredItem, config: &SmartSyncConfig) -> SyncStrategy {
+        self.0
+            .iter()
+            .find(|r| (!r.dir_only || item.is_dir) && r.matcher.is_match(&item.rel_path))
+            .map(|r| r.strategy)
+            .unwrap_or(if item.is_dir { SyncStrategy::Symlink } else { config.file_mode })
+    }
+}
+
+// A path git considers ignored, relative to the source checkout.
+#[derive(Debug, Clone)]
+pub struct IgnoredItem {
+    pub rel_path: String,
+    pub is_dir: bool,
+}
+
+// Asks git which untracked paths are ignored, so every gitignore rule applies:
+// globs, negations, anchored patterns, nested .gitignore files,
+// .git/info/exclude and core.excludesFile.
+// With --directory an ignored directory is reported once (with a trailing '/')
+// instead of file by file, which is exactly the granularity we link at.
+pub fn discover_ignored(source_path: &str) -> Result<Vec<IgnoredItem>, AppError> {
+    let output = run_git(source_path, &["ls-files", "--others", "--ignored", "--exclude-standard", "--directory", "-z"])?;
+
+    let mut paths: Vec<&str> = output.split('\0').filter(|p| !p.is_empty()).collect();
+    paths.sort_unstable();
+
+    // An untracked directory holding only ignored entries is reported alongside those
+    // entries (e.g. "pkg/" and "pkg/node_modules/"). It is a container, not an ignore
+    // target itself, so keep only the innermost entries.
+    let is_container = |i: usize| paths[i].ends_with('/') && paths.get(i + 1).is_some_and(|next| next.starts_with(paths[i]));
+
+    Ok((0..paths.len())
+        .filter(|&i| !is_container(i))
+        .map(|i| match paths[i].strip_suffix('/') {
+            Some(dir) => IgnoredItem { rel_path: dir.to_string(), is_dir: true },
+            None => IgnoredItem { rel_path: paths[i].to_string(), is_dir: false },
+        })
+        .collect())
+}
+
+fn config_path(project_path: &str) -> Result<PathBuf, AppError> {
+    Ok(manager_dir(project_path)?.join("smart-sync.json"))
+}
+
+pub fn load_config(project_path: &str) -> Result<SmartSyncConfig, AppError> {
+    let path = config_path(project_path)?;
+    if !path.exists() {
+        return Ok(SmartSyncConfig::default());
+    }
+    let content = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&content).map_err(|e| AppError::InvalidFile { path: path.display().to_string(), reason: e.to_string() })
+}
+
+#[tauri::command]
+pub async fn get_smart_sync_config(project_path: String) -> Result<SmartSyncConfig, AppError> {
+    run_blocking(move || load_config(&project_path)).await
+}
+
+#[tauri::command]
+pub async fn set_smart_sync_config(project_path: String, config: SmartSyncConfig) -> Result<(), AppError> {
+    run_blocking(move || save_config(&project_path, &config)).await
+}
+
+fn save_config(project_path: &str, config: &SmartSyncConfig) -> Result<(), AppError> {
+    let path = config_path(project_path)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(config).map_err(|e| AppError::Other(e.to_string()))?;
+    std::fs::write(&path, content).map_err(|e| AppError::io(format!("Failed to write '{}'", path.display()), e))
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(src: &Path, dest: &Path) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let src_file = std::fs::File::open(src)?;
+    let dest_file = std::fs::OpenOptions::new().write(true).create_new(true).open(dest)?;
+    // SAFETY: both descriptors are open for the duration of the call
+    if unsafe { libc::ioctl(dest_file.as_raw_fd(), libc::FICLONE, src_file.as_raw_fd()) } == -1 {
+        let err = std::io::Error::last_os_error();
+        drop(dest_file);
+        let _ = std::fs::remove_file(dest);
+        return Err(err);
+    }
+    std::fs::set_permissions(dest, src_file.metadata()?.permissions())
+}
+
+#[cfg(target_os = "macos")]
+fn reflink(src: &Path, dest: &Path) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let src = CString::new(src.as_os_str().as_bytes())?;
+    let dest = CString::new(dest.as_os_str().as_bytes())?;
+    // SAFETY: both arguments are valid NUL-terminated paths
+    if unsafe { libc::clonefile(src.as_ptr(), dest.as_ptr(), 0) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_src: &Path, _dest: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "reflinks are not supported on this platform"))
+}
+
+// What a sync step actually did. Reflinks (and symlinks on Windows) fall back
+// to weaker strategies, and `bytes` counts data materialized in the worktree.
+#[derive(Debug, Clone, Copy)]
+struct Synced {
+    used: SyncStrategy,
+    bytes: u64,
+}
+
+// Brings one file over.
+fn sync_file(src: &Path, dest: &Path, strategy: SyncStrategy) -> std::io::Result<Synced> {
+    let synced = |used, bytes| Synced { used, bytes };
+    match strategy {
+        #[cfg(unix)]
+        SyncStrategy::Symlink => std::os::unix::fs::symlink(src, dest).map(|_| synced(SyncStrategy::Symlink, 0)),
+        // Symlinks need Developer Mode or admin on Windows, so fall back rather than fail
+        #[cfg(target_os = "windows")]
+        SyncStrategy::Symlink => std::os::windows::fs::symlink_file(src, dest)
+            .map(|_| synced(SyncStrategy::Symlink, 0))
+            .or_else(|_| std::fs::hard_link(src, dest).and_then(|_| dest.metadata()).map(|m| synced(SyncStrategy::Hardlink, m.len())))
+            .or_else(|_| std::fs::copy(src, dest).map(|n| synced(SyncStrategy::Copy, n))),
+        SyncStrategy::Hardlink => std::fs::hard_link(src, dest)
+            .and_then(|_| dest.metadata())
+            .map(|m| synced(SyncStrategy::Hardlink, m.len())),
+        SyncStrategy::Reflink => reflink(src, dest)
+            .and_then(|_| dest.metadata())
+            .map(|m| synced(SyncStrategy::Reflink, m.len()))
+            .or_else(|_| std::fs::copy(src, dest).map(|n| synced(SyncStrategy::Copy, n))),
+        SyncStrategy::Copy => std::fs::copy(src, dest).map(|n| synced(SyncStrategy::Copy, n)),
+        SyncStrategy::Skip => Ok(synced(SyncStrategy::Skip, 0)),
+    }
+}
+
+// Recreates a directory file by file, spread across threads. Symlinks inside are reproduced, not followed.
+// `dest` must not exist yet: on failure (or cancellation) it is removed again, so
+// a half-populated folder is never taken for a synced one.
+fn sync_tree(src: &Path, dest: &Path, strategy: SyncStrategy, cancel: Option<&CancelFlag>) -> std::io::Result<Synced> {
+    std::fs::create_dir_all(dest)?;
+    let bytes = AtomicU64::new(0);
+    let used = Mutex::new(strategy);
+    let options = WalkOptions { cancel: cancel.cloned(), ..Default::default() };
+
+    let walked = walk::walk(src, &options, |entry| {
+        let Ok(rel) = entry.path.strip_prefix(src) else {
+            return Ok(());
+        };
+        let target = dest.join(rel);
+        // The walker visits a directory before anything inside it, so parents always exist here
+        let synced = if entry.file_type.is_dir() {
+            std::fs::create_dir_all(&target)?;
+            return Ok(());
+        } else if entry.file_type.is_symlink() {
+            copy_symlink(&entry.path, &target)?
+        } else {
+            sync_file(&entry.path, &target, strategy)?
+        };
+        // Report the weakest strategy any file needed
+        if synced.used != strategy && synced.used != SyncStrategy::Symlink {
+            *used.lock().unwrap() = synced.used;
+        }
+        bytes.fetch_add(synced.bytes, Ordering::Relaxed);
+        Ok(())
+    });
+    if let Err(e) = walked {
+        if let Err(cleanup) = std::fs::remove_dir_all(dest) {
+            println!("Failed to remove the partial copy at {}: {}", dest.display(), cleanup);
+        }
+        return Err(e);
+    }
+
+    Ok(Synced { used: used.into_inner().unwrap(), bytes: bytes.into_inner() })
+}
+
+#[cfg(unix)]
+fn copy_symlink(src: &Path, dest: &Path) -> std::io::Result<Synced> {
+    std::os::unix::fs::symlink(std::fs::read_link(src)?, dest)?;
+    Ok(Synced { used: SyncStrategy::Symlink, bytes: 0 })
+}
+
+#[cfg(target_os = "windows")]
+fn copy_symlink(src: &Path, dest: &Path) -> std::io::Result<Synced> {
+    // Recreating links needs privileges on Windows; copy what they point at instead
+    if src.is_dir() {
+        sync_tree(src, dest, SyncStrategy::Copy, None)
+    } else {
+        std::fs::copy(src, dest).map(|bytes| Synced { used: SyncStrategy::Copy, bytes })
+    }
+}
+
+// Outcome for one ignored item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncItemReport {
+    pub source: String,
+    pub destination: String,
+    pub is_dir: bool,
+    // What the rules asked for
+    pub strategy: SyncStrategy,
+    // What was actually done; None when nothing was created (already present or failed)
+    pub used: Option<SyncStrategy>,
+    // Data materialized in the worktree; 0 for symlinks, which share the source
+    pub bytes: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub source_path: String,
+    pub worktree_path: String,
+    pub created_at: u64, // Unix seconds
+    pub items: Vec<SyncItemReport>,
+    // Set when the sync could not start at all (e.g. git or the config failed)
+    pub error: Option<String>,
+    // Set when lockfiles diverged from the source
+    pub bootstrap: Option<BootstrapReport>,
+}
+
+// Per-worktree state is kept in the worktree's private git dir, next to git's own metadata for it.
+fn worktree_meta_dir(worktree_path: &str) -> Result<PathBuf, AppError> {
+    let git_dir = run_git(worktree_path, &["rev-parse", "--absolute-git-dir"])?;
+    Ok(Path::new(git_dir.trim()).join("worktree-manager"))
+}
+
+fn report_path(worktree_path: &str) -> Result<PathBuf, AppError> {
+    Ok(worktree_meta_dir(worktree_path)?.join("smart-sync-report.json"))
+}
+
+// A link Smart Sync placed in a worktree. Everything listed here shares data
+// with the source, so it is unlinked explicitly before the worktree is deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub source: String,
+    pub destination: String,
+    pub is_dir: bool,
+    // Symlink (junction for Windows directories) or Hardlink
+    pub kind: SyncStrategy,
+}
+
+fn manifest_path(worktree_path: &str) -> Result<PathBuf, AppError> {
+    Ok(worktree_meta_dir(worktree_path)?.join("smart-sync-manifest.json"))
+}
+
+fn load_manifest(worktree_path: &str) -> Result<Vec<ManifestEntry>, AppError> {
+    let path = manifest_path(worktree_path)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&content).map_err(|e| AppError::InvalidFile { path: path.display().to_string(), reason: e.to_string() })
+}
+
+fn save_manifest(worktree_path: &str, entries: &[ManifestEntry]) -> Result<(), AppError> {
+    let path = manifest_path(worktree_path)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(entries).map_err(|e| AppError::Other(e.to_string()))?;
+    std::fs::write(&path, content).map_err(|e| AppError::io(format!("Failed to write '{}'", path.display()), e))
+}
+
+// git's exclude file. info/ belongs to the common dir, so every worktree of the
+// repository reads the same one.
+fn exclude_path(worktree_path: &str) -> Result<PathBuf, AppError> {
+    let path = run_git(worktree_path, &["rev-parse", "--git-path", "info/exclude"])?;
+    Ok(Path::new(worktree_path).join(path.trim()))
+}
+
+// "/node_modules" for <worktree>/node_modules: anchored, with glob characters escaped.
+fn exclude_pattern(worktree_path: &str, destination: &str) -> Option<String> {
+    let relative = Path::new(destination).strip_prefix(worktree_path).ok()?;
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    let mut pattern = String::from("/");
+    for c in relative.chars() {
+        if matches!(c, '*' | '?' | '[' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    Some(pattern)
+}
+
+const EXCLUDE_BEGIN: &str = "# git-worktree-manager links of ";
+const EXCLUDE_END: &str = "# end of git-worktree-manager links";
+
+// Lists the worktree's Smart Sync links in git's exclude file. git does not match
+// a symlink against a directory rule like `node_modules/`, so without this every
+// link shows up as untracked: in statuses, removal checks and trash snapshots.
+// Each worktree gets its own block, named after its private git dir; blocks of
+// worktrees that no longer exist are dropped. The anchored patterns apply to the
+// other worktrees too, where those paths are ignored already (that is why they were synced).
+fn update_excludes(worktree_path: &str, manifest: &[ManifestEntry]) -> Result<(), AppError> {
+    let path = exclude_path(worktree_path)?;
+    let owner = worktree_meta_dir(worktree_path)?.parent().map(|p| p.display().to_string()).unwrap_or_default();
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(AppError::io(format!("Failed to read '{}'", path.display()), e)),
+    };
+
+    let mut kept = Vec::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let Some(block_owner) = line.strip_prefix(EXCLUDE_BEGIN) else {
+            kept.push(line);
+            continue;
+        };
+        let block: Vec<&str> = lines.by_ref().take_while(|l| *l != EXCLUDE_END).collect();
+        if block_owner != owner && Path::new(block_owner).exists() {
+            kept.push(line);
+            kept.extend(block);
+            kept.push(EXCLUDE_END);
+        }
+    }
+
+    let patterns: Vec<String> = manifest.iter().filter_map(|m| exclude_pattern(worktree_path, &m.destination)).collect();
+    let mut updated = kept.join("\n");
+    if !patterns.is_empty() {
+        if !updated.is_empty() {
+            updated.push('\n');
+        }
+        updated.push_str(&format!("{}{}\n{}\n{}", EXCLUDE_BEGIN, owner, patterns.join("\n"), EXCLUDE_END));
+    }
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    if updated == content {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, updated).map_err(|e| AppError::io(format!("Failed to write '{}'", path.display()), e))
+}
+
+// Smart Sync links of a worktree, relative to it with '/' separators, the way
+// `git status` names them.
+pub fn linked_paths(worktree_path: &str) -> Vec<String> {
+    load_manifest(worktree_path)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|m| Path::new(&m.destination).strip_prefix(worktree_path).ok().map(|p| p.to_string_lossy().replace('\\', "/")))
+        .collect()
+}
+
+// Adds the links a sync run created to the worktree's manifest. A destination
+// linked again (e.g. from another source) replaces its old entry.
+fn record_links(report: &SyncReport) -> Result<(), AppError> {
+    let mut manifest = load_manifest(&report.worktree_path)?;
+    for item in &report.items {
+        if let Some(kind @ (SyncStrategy::Symlink | SyncStrategy::Hardlink)) = item.used {
+            manifest.retain(|m| m.destination != item.destination);
+            manifest.push(ManifestEntry {
+                source: item.source.clone(),
+                destination: item.destination.clone(),
+                is_dir: item.is_dir,
+                kind,
+            });
+        }
+    }
+    save_manifest(&report.worktree_path, &manifest)?;
+    update_excludes(&report.worktree_path, &manifest)
+}
+
+// Removes a link itself, never what it points at.
+fn remove_link(path: &Path) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        // Directory symlinks and junctions are removed as directories
+        std::fs::remove_dir(path).or_else(|_| std::fs::remove_file(path))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        std::fs::remove_file(path)
+    }
+}
+
+// A link taken out before a removal, with what it led to, so it can be put
+// back if git then refuses to remove the worktree.
+#[derive(Debug, Clone)]
+pub struct RemovedLink {
+    path: PathBuf,
+    target: PathBuf,
+    hardlink: bool,
+}
+
+impl RemovedLink {
+    // Reads what a symlink (or junction) points at, before it is removed.
+    fn symlink(path: &Path) -> std::io::Result<Self> {
+        Ok(RemovedLink {
+            target: std::fs::read_link(path)?,
+            path: path.to_path_buf(),
+            hardlink: false,
+        })
+    }
+
+    fn restore(&self) -> std::io::Result<()> {
+        if self.hardlink {
+            return std::fs::hard_link(&self.target, &self.path);
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&self.target, &self.path)
+        }
+        #[cfg(target_os = "windows")]
+        {
+            // Directories were junctions, which always hold an absolute target
+            if !self.target.is_dir() {
+                return std::os::windows::fs::symlink_file(&self.target, &self.path);
+            }
+            let output = create_command("cmd")
+                .arg("/C")
+                .arg(format!("mklink /J \"{}\" \"{}\"", self.path.display(), self.target.display()))
+                .output()?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+            }
+        }
+    }
+}
+
+// Removes the symlinks (and junctions) among the worktree's untracked items that
+// lead outside `root`, e.g. links made by hand or before there was a manifest.
+// Git lists an untracked folder once, so nothing inside copied or installed
+// dependencies is visited.
+fn unlink_escaping_links(root: &Path, removed: &mut Vec<RemovedLink>) -> std::io::Result<()> {
+    let worktree_path = root.display().to_string();
+    // No --exclude-standard: ignored or not, every untracked item could be a link
+    let untracked: Vec<PathBuf> = match run_git(&worktree_path, &["ls-files", "--others", "--directory", "-z"]) {
+        Ok(output) => output.split('\0').filter(|p| !p.is_empty()).map(|p| root.join(p.trim_end_matches('/'))).collect(),
+        // Without git, the top level at least
+        Err(_) => std::fs::read_dir(root)?.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+    };
+    for path in untracked {
+        // symlink_metadata does not follow links, so a junction reports as a link here
+        if !path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+            continue;
+        }
+        let escapes = path.canonicalize().map(|t| !t.starts_with(root)).unwrap_or(false);
+        if escapes {
+            let link = RemovedLink::symlink(&path)?;
+            remove_link(&path)?;
+            removed.push(link);
+        }
+    }
+    Ok(())
+}
+
+// Makes a worktree safe to delete recursively: every link from the manifest is
+// unlinked first (after checking it really lives inside the worktree and is
+// still a link), then any other untracked link leading out of it is removed too.
+// After this, no recursive delete - by git or by us - can reach the main
+// checkout through a link. Returns the links taken out, for `relink`.
+pub fn unlink_before_removal(worktree_path: &str) -> Result<Vec<RemovedLink>, AppError> {
+    let root = Path::new(worktree_path)
+        .canonicalize()
+        .map_err(|e| AppError::io(format!("Cannot resolve worktree '{}'", worktree_path), e))?;
+    let mut removed = Vec::new();
+
+    for entry in load_manifest(worktree_path)? {
+        let dest = Path::new(&entry.destination);
+        let Ok(meta) = dest.symlink_metadata() else {
+            continue; // Already gone
+        };
+
+        // Resolve the parent only, so the check never follows the link itself
+        let parent = dest
+            .parent()
+            .and_then(|p| p.canonicalize().ok())
+            .ok_or_else(|| AppError::NotFound(format!("Cannot resolve parent of '{}'", entry.destination)))?;
+        if !parent.starts_with(&root) {
+            return Err(AppError::OutsideWorktree { path: entry.destination.clone(), worktree: root.display().to_string() });
+        }
+
+        let unlink_error = |e| AppError::io(format!("Failed to unlink '{}'", entry.destination), e);
+        if meta.file_type().is_symlink() {
+            let link = RemovedLink::symlink(dest).map_err(unlink_error)?;
+            remove_link(dest).map_err(unlink_error)?;
+            removed.push(link);
+        } else if entry.kind == SyncStrategy::Hardlink && meta.is_file() {
+            remove_link(dest).map_err(unlink_error)?;
+            removed.push(RemovedLink { path: dest.to_path_buf(), target: PathBuf::from(&entry.source), hardlink: true });
+        }
+    }
+
+    unlink_escaping_links(&root, &mut removed)
+        .map_err(|e| AppError::io(format!("Failed to unlink links in '{}'", root.display()), e))?;
+    // The links are gone, so are their exclude entries
+    if let Err(e) = update_excludes(worktree_path, &[]) {
+        println!("Failed to drop the exclude entries of {}: {}", worktree_path, e);
+    }
+    Ok(removed)
+}
+
+// Puts back the links `unlink_before_removal` took out, along with their
+// exclude entries, for a worktree that is staying after all.
+pub fn relink(worktree_path: &str, links: &[RemovedLink]) -> Result<(), AppError> {
+    let mut failed = None;
+    for link in links {
+        if let Err(e) = link.restore() {
+            println!("Failed to relink {}: {}", link.path.display(), e);
+            failed.get_or_insert(AppError::io(format!("Failed to relink '{}'", link.path.display()), e));
+        }
+    }
+    update_excludes(worktree_path, &load_manifest(worktree_path)?)?;
+    failed.map_or(Ok(()), Err)
+}
+
+pub fn save_report(report: &SyncReport) -> Result<(), AppError> {
+    let path = report_path(&report.worktree_path)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(report).map_err(|e| AppError::Other(e.to_string()))?;
+    std::fs::write(&path, content).map_err(|e| AppError::io(format!("Failed to write '{}'", path.display()), e))
+}
+
+// Returns the report of the Smart Sync runs for a worktree, if any.
+#[tauri::command]
+pub async fn get_sync_report(worktree_path: String) -> Result<Option<SyncReport>, AppError> {
+    run_blocking(move || load_report(&worktree_path)).await
+}
+
+pub fn load_report(worktree_path: &str) -> Result<Option<SyncReport>, AppError> {
+    let path = report_path(worktree_path)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| AppError::InvalidFile { path: path.display().to_string(), reason: e.to_string() })
+}
+
+// An existing worktree that could serve as the Smart Sync source, compared
+// against the target worktree's lockfiles.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceCandidate {
+    pub path: String,
+    pub branch: Option<String>,
+    // Lockfiles identical to the target's
+    pub matching: Vec<String>,
+    // Lockfiles both have, with different content
+    pub differing: Vec<String>,
+    // Lockfiles the target has but this candidate lacks
+    pub missing: Vec<String>,
+}
+
+// Every usable worktree of the repository, best source first: most identical
+// lockfiles, then fewest diverging ones. Ties keep `git worktree list` order,
+// so the main checkout wins when nothing tells them apart.
+fn rank_sources(project_path: &str, worktree_path: &str) -> Result<Vec<SourceCandidate>, AppError> {
+    let target = bootstrap::lockfile_hashes(worktree_path);
+    let target_dir = Path::new(worktree_path).canonicalize().ok();
+
+    let mut candidates: Vec<SourceCandidate> = backend::current().list_worktrees(Path::new(project_path))?
+        .into_iter()
+        .filter(|wt| !wt.is_bare && !wt.is_prunable && Path::new(&wt.path).is_dir())
+        .filter(|wt| Path::new(&wt.path).canonicalize().ok() != target_dir)
+        .map(|wt| {
+            let hashes = bootstrap::lockfile_hashes(&wt.path);
+            let mut candidate = SourceCandidate {
+                path: wt.path.clone(),
+                branch: wt.branch.clone(),
+                matching: Vec::new(),
+                differing: Vec::new(),
+                missing: Vec::new(),
+            };
+            for (name, id) in &target {
+                match hashes.iter().find(|(n, _)| n == name) {
+                    Some((_, other)) if other == id => candidate.matching.push(name.clone()),
+                    Some(_) => candidate.differing.push(name.clone()),
+                    None => candidate.missing.push(name.clone()),
+                }
+            }
+            candidate
+        })
+        .collect();
+
+    candidates.sort_by_key(|c| (std::cmp::Reverse(c.matching.len()), c.differing.len()));
+    Ok(candidates)
+}
+
+// Other worktrees of the repository with Smart Sync links into `worktree_path`,
+// which removing it would break. Links are resolved to their ultimate source
+// now, but older ones (or a source worktree that was itself a link) remain.
+pub fn linked_from(project_path: &str, worktree_path: &str) -> Vec<String> {
+    let Ok(root) = Path::new(worktree_path).canonicalize() else {
+        return Vec::new();
+    };
+    let Ok(worktrees) = backend::current().list_worktrees(Path::new(project_path)) else {
+        return Vec::new();
+    };
+    worktrees
+        .into_iter()
+        .filter(|wt| Path::new(&wt.path).canonicalize().is_ok_and(|p| p != root))
+        .filter(|wt| {
+            load_manifest(&wt.path).unwrap_or_default().iter().any(|e| {
+                e.kind == SyncStrategy::Symlink && Path::new(&e.source).canonicalize().is_ok_and(|s| s.starts_with(&root))
+            })
+        })
+        .map(|wt| wt.path)
+        .collect()
+}
+
+// The worktree whose dependencies best fit `worktree_path`, falling back to the project itself.
+pub fn pick_source(project_path: &str, worktree_path: &str) -> String {
+    match rank_sources(project_path, worktree_path) {
+        Ok(candidates) => candidates.into_iter().next().map(|c| c.path).unwrap_or_else(|| project_path.to_string()),
+        Err(e) => {
+            println!("Failed to rank Smart Sync sources for {}: {}", worktree_path, e);
+            project_path.to_string()
+        }
+    }
+}
+
+// Possible Smart Sync sources for an existing worktree, best match first.
+#[tauri::command]
+pub async fn sync_source_candidates(project_path: String, worktree_path: String) -> Result<Vec<SourceCandidate>, AppError> {
+    run_blocking(move || rank_sources(&project_path, &worktree_path)).await
+}
+
+// Brings every ignored item of the source checkout (the project root or another
+// worktree) into the new worktree using the strategy its rule selects.
+// Unmatched directories become symlinks (junctions on Windows) and unmatched
+// files follow `file_mode`. Dependency folders whose lockfile differs from the
+// source's are installed or copied instead, per `lockfile_mismatch`; installs
+// keep running in the background after this returns.
+// The report is persisted with the worktree and returned.
+pub fn link_gitignored_items(op: &Operation, source_path: &str, worktree_path: &str) -> SyncReport {
+    let mut report = new_report(source_path, worktree_path);
+    let mut install = None;
+
+    op.report(Phase::Discover, 0, None, Some(source_path));
+    match plan_for_sync(source_path, worktree_path) {
+        Ok((plan, bootstrap)) => {
+            if let Some((bootstrap, deferred)) = bootstrap {
+                if let Some(command) = bootstrap.command.clone() {
+                    install = Some((command, deferred));
+                }
+                report.bootstrap = Some(bootstrap);
+            }
+            sync_items(plan, &mut report, Some(op));
+        }
+        Err(e) => report.error = Some(e.to_string()),
+    }
+    if let Err(e) = save_report(&report) {
+        println!("Failed to save Smart Sync report for {}: {}", worktree_path, e);
+    }
+    if let Err(e) = record_links(&report) {
+        // Without a manifest, removal still falls back to unlinking every link it finds
+        println!("Failed to record Smart Sync manifest for {}: {}", worktree_path, e);
+    }
+    if let Some((command, deferred)) = install.filter(|_| !op.is_cancelled()) {
+        bootstrap::spawn_install(op.app().clone(), worktree_path.to_string(), command, deferred);
+    }
+    report
+}
+
+// The sync plan with lockfile divergence applied, plus what the bootstrap needs, if anything.
+fn plan_for_sync(source_path: &str, worktree_path: &str) -> Result<(Vec<PlannedItem>, Option<Bootstrap>), AppError> {
+    plan_against(source_path, worktree_path, bootstrap::lockfile_hashes(worktree_path))
+}
+
+// Same, with the worktree's lockfiles given, as they are for one not created yet.
+fn plan_against(source_path: &str, worktree_path: &str, lockfiles: Vec<(String, String)>) -> Result<(Vec<PlannedItem>, Option<Bootstrap>), AppError> {
+    let mut plan = plan_items(source_path, worktree_path)?;
+    let config = load_config(source_path)?;
+    let bootstrap = bootstrap::detect(source_path, lockfiles, &config).and_then(|d| d.apply(worktree_path, &mut plan));
+    Ok((plan, bootstrap))
+}
+
+fn new_report(project_path: &str, worktree_path: &str) -> SyncReport {
+    SyncReport {
+        source_path: project_path.to_string(),
+        worktree_path: worktree_path.to_string(),
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        ..Default::default()
+    }
+}
+
+// Brings over ignored items that appeared in the main checkout after the
+// worktree was created (a fresh `dist`, a new package's `node_modules`).
+// Whatever the worktree already has is left alone. Links come from the same
+// source as before unless another one is given. The returned report only
+// lists this run's work; the persisted one is updated to cover everything.
+#[tauri::command]
+pub async fn resync_worktree(project_path: String, worktree_path: String, source: Option<String>) -> Result<SyncReport, AppError> {
+    run_blocking(move || resync(project_path, worktree_path, source)).await
+}
+
+fn resync(project_path: String, worktree_path: String, source: Option<String>) -> Result<SyncReport, AppError> {
+    if !Path::new(&worktree_path).exists() {
+        return Err(AppError::NotFound(format!("Worktree '{}' does not exist", worktree_path)));
+    }
+    let previous = load_report(&worktree_path)?;
+    let known = |dest: &str| previous.as_ref().is_some_and(|p| p.items.iter().any(|i| i.destination == dest));
+
+    let source = source
+        .filter(|s| !s.is_empty())
+        .or_else(|| previous.as_ref().map(|p| p.source_path.clone()).filter(|s| Path::new(s).is_dir()))
+        .unwrap_or(project_path);
+    // Dependency folders with a diverged lockfile are copied or left out here; re-sync never starts an install
+    let (plan, _) = plan_for_sync(&source, &worktree_path)?;
+    let mut report = new_report(&source, &worktree_path);
+    sync_items(plan, &mut report, None);
+    report.items.retain(|item| match item.used {
+        // Already present, unless it failed this time
+        None => item.error.is_some(),
+        Some(SyncStrategy::Skip) => !known(&item.destination),
+        Some(_) => true,
+    });
+
+    let mut merged = previous.unwrap_or_else(|| new_report(&source, &worktree_path));
+    merged.source_path = source;
+    for item in &report.items {
+        match merged.items.iter_mut().find(|i| i.destination == item.destination) {
+            Some(existing) => *existing = item.clone(),
+            None => merged.items.push(item.clone()),
+        }
+    }
+    save_report(&merged)?;
+    record_links(&report)?;
+    Ok(report)
+}
+
+// Replaces a Smart Sync link in a worktree with a real, independent copy of its
+// source, e.g. so an agent can patch dependencies without touching the other
+// worktrees. `destination` is the linked path, absolute or relative to the worktree.
+#[tauri::command]
+pub async fn materialize_item(worktree_path: String, destination: String) -> Result<SyncItemReport, AppError> {
+    run_blocking(move || materialize(worktree_path, destination)).await
+}
+
+fn materialize(worktree_path: String, destination: String) -> Result<SyncItemReport, AppError> {
+    let dest = if Path::new(&destination).is_absolute() {
+        PathBuf::from(&destination)
+    } else {
+        Path::new(&worktree_path).join(&destination)
+    };
+
+    let mut manifest = load_manifest(&worktree_path)?;
+    let pos = manifest
+        .iter()
+        .position(|m| Path::new(&m.destination) == dest)
+        .ok_or_else(|| AppError::NotFound(format!("'{}' is not a Smart Sync link", destination)))?;
+    let entry = manifest[pos].clone();
+    let src = Path::new(&entry.source);
+
+    let root = Path::new(&worktree_path)
+        .canonicalize()
+        .map_err(|e| AppError::io(format!("Cannot resolve worktree '{}'", worktree_path), e))?;
+    let parent = dest.parent().and_then(|p| p.canonicalize().ok());
+    if !parent.is_some_and(|p| p.starts_with(&root)) {
+        return Err(AppError::OutsideWorktree { path: dest.display().to_string(), worktree: root.display().to_string() });
+    }
+
+    // Build the copy next to the link first, so a failure leaves the link in place
+    let file_name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let staging = dest.with_file_name(format!("{}.gwm-materialize", file_name));
+    let clear_staging = || {
+        let _ = std::fs::remove_dir_all(&staging);
+        let _ = std::fs::remove_file(&staging);
+    };
+    clear_staging();
+    let copied = if entry.is_dir {
+        sync_tree(src, &staging, SyncStrategy::Copy, None)
+    } else {
+        sync_file(src, &staging, SyncStrategy::Copy)
+    };
+    let synced = copied.map_err(|e| {
+        clear_staging();
+        AppError::io(format!("Failed to copy '{}'", entry.source), e)
+    })?;
+
+    // Hardlinked directories are real directories of shared files; unlinking those files leaves the source intact
+    let unlinked = match entry.kind {
+        SyncStrategy::Hardlink if entry.is_dir => std::fs::remove_dir_all(&dest),
+        _ => remove_link(&dest),
+    };
+    if let Err(e) = unlinked.and_then(|_| std::fs::rename(&staging, &dest)) {
+        clear_staging();
+        return Err(AppError::io(format!("Failed to replace '{}' with its copy", dest.display()), e));
+    }
+
+    manifest.remove(pos);
+    save_manifest(&worktree_path, &manifest)?;
+    // A real copy is matched by the gitignore rules again
+    update_excludes(&worktree_path, &manifest)?;
+
+    let mut materialized = SyncItemReport {
+        source: entry.source.clone(),
+        destination: entry.destination.clone(),
+        is_dir: entry.is_dir,
+        strategy: entry.kind,
+        used: Some(synced.used),
+        bytes: synced.bytes,
+        error: None,
+    };
+    if let Some(mut report) = load_report(&worktree_path)? {
+        if let Some(item) = report.items.iter_mut().find(|i| i.destination == entry.destination) {
+            item.used = materialized.used;
+            item.bytes = materialized.bytes;
+            item.error = None;
+            materialized.strategy = item.strategy;
+        }
+        save_report(&report)?;
+    }
+    Ok(materialized)
+}
+
+// One ignored item and what Smart Sync would do with it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedItem {
+    pub source: String,
+    pub destination: String,
+    pub is_dir: bool,
+    pub strategy: SyncStrategy,
+    // Destination already present, so the item would be left alone
+    pub exists: bool,
+    // Size of the source; only filled in by `plan_smart_sync`
+    pub estimated_bytes: u64,
+}
+
+// Discovery shared by the real sync and the dry run: ignored items, the strategy
+// each one gets and whether the destination is already there. Touches nothing.
+fn plan_items(project_path: &str, worktree_path: &str) -> Result<Vec<PlannedItem>, AppError> {
+    let project_dir = Path::new(project_path);
+    let worktree_dir = Path::new(worktree_path);
+
+    let items = discover_ignored(project_path)?;
+    let config = load_config(project_path)?;
+    let rules = RuleSet::new(&config.rules)?;
+    // The source may be a worktree with Smart Sync links of its own. Git lists a
+    // linked folder as a file, so take each link's own source instead: rules see
+    // the real kind of item, and worktrees never link through one another.
+    let links: Vec<ManifestEntry> = load_manifest(project_path)?
+        .into_iter()
+        .filter(|e| e.kind == SyncStrategy::Symlink)
+        .collect();
+
+    Ok(items
+        .iter()
+        // Never link a worktree into itself (e.g. worktrees kept in an ignored folder of the repo)
+        .filter(|item| !worktree_dir.starts_with(project_dir.join(&item.rel_path)))
+        .map(|item| {
+            let dest_path = worktree_dir.join(&item.rel_path);
+            let mut source = project_dir.join(&item.rel_path);
+            let mut item = item.clone();
+            let is_link = source.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink());
+            if let Some(link) = links.iter().find(|e| is_link && Path::new(&e.destination) == source) {
+                // Manifests written before sources were resolved can still chain
+                source = Path::new(&link.source).canonicalize().unwrap_or_else(|_| PathBuf::from(&link.source));
+                item.is_dir = link.is_dir;
+            }
+            PlannedItem {
+                source: source.display().to_string(),
+                destination: dest_path.display().to_string(),
+                is_dir: item.is_dir,
+                strategy: rules.strategy_for(&item, &config),
+                // symlink_metadata so a dangling link still counts as present
+                exists: dest_path.symlink_metadata().is_ok(),
+                estimated_bytes: 0,
+            }
+        })
+        .collect())
+}
+
+// Total size of the files under `path`, without following symlinks.
+fn disk_usage(path: &Path, cancel: Option<&CancelFlag>) -> u64 {
+    let Ok(meta) = path.symlink_metadata() else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    let total = AtomicU64::new(0);
+    let options = WalkOptions { skip_unreadable: true, cancel: cancel.cloned(), ..Default::default() };
+    let _ = walk::walk(path, &options, |entry| {
+        if !entry.file_type.is_dir() {
+            let len = entry.path.symlink_metadata().map(|m| m.len()).unwrap_or(0);
+            total.fetch_add(len, Ordering::Relaxed);
+        }
+        Ok(())
+    });
+    total.into_inner()
+}
+
+// Result of the dry run: the items, and how dependency folders with a
+// diverged lockfile would be bootstrapped.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncPlan {
+    pub items: Vec<PlannedItem>,
+    pub bootstrap: Option<BootstrapReport>,
+}
+
+// Dry run of Smart Sync: what would be linked, copied, installed or skipped,
+// with sizes. `source` defaults to the project itself. `rev` is what the
+// worktree will check out, for comparing lockfiles before it exists. Reports
+// progress under `operation_id` and can be cancelled through it.
+#[tauri::command]
+pub async fn plan_smart_sync(
+    app: AppHandle,
+    operations: State<'_, Operations>,
+    project_path: String,
+    worktree_path: String,
+    source: Option<String>,
+    rev: Option<String>,
+    operation_id: Option<String>,
+) -> Result<SyncPlan, AppError> {
+    let op = operations.begin(app, operation_id);
+    let source = source.filter(|s| !s.is_empty()).unwrap_or(project_path);
+    run_blocking(move || plan_with_sizes(&op, &source, &worktree_path, rev.as_deref())).await
+}
+
+pub fn plan_with_sizes(op: &Operation, source: &str, worktree_path: &str, rev: Option<&str>) -> Result<SyncPlan, AppError> {
+    op.report(Phase::Discover, 0, None, Some(source));
+    let lockfiles = match rev.filter(|r| !r.is_empty() && !Path::new(worktree_path).exists()) {
+        Some(rev) => bootstrap::lockfile_hashes_at(source, rev),
+        None => bootstrap::lockfile_hashes(worktree_path),
+    };
+    let (mut plan, bootstrap) = plan_against(source, worktree_path, lockfiles)?;
+
+    // One item at a time: each walk already uses every core, and one huge node_modules
+    // would leave most of them idle if items were split between threads instead
+    let total = plan.len();
+    let cancel = op.cancel_flag();
+    for (i, item) in plan.iter_mut().enumerate() {
+        op.report(Phase::Size, i, Some(total), Some(&item.source));
+        item.estimated_bytes = disk_usage(Path::new(&item.source), Some(&cancel));
+        op.check()?;
+    }
+
+    Ok(SyncPlan { items: plan, bootstrap: bootstrap.map(|(report, _)| report) })
+}
+
+// Carries out a plan, adding one entry per item to the report. With an
+// operation, progress is reported and cancellation stops after the current item.
+pub fn sync_items(plan: Vec<PlannedItem>, report: &mut SyncReport, op: Option<&Operation>) {
+    let total = plan.len();
+    let cancel = op.map(|op| op.cancel_flag());
+
+    // Collect failed junctions to run in one elevated batch (index into report.items)
+    #[cfg(target_os = "windows")]
+    let mut pending_admin_links: Vec<(String, usize)> = Vec::new();
+
+    for (i, item) in plan.into_iter().enumerate() {
+        if let Some(op) = op {
+            if op.is_cancelled() {
+                break;
+            }
+            op.report(Phase::Sync, i, Some(total), Some(&item.destination));
+        }
+        let src_path = Path::new(&item.source);
+        let dest_path = Path::new(&item.destination);
+        let strategy = item.strategy;
+        let mut entry = SyncItemReport {
+            source: item.source.clone(),
+            destination: item.destination.clone(),
+            is_dir: item.is_dir,
+            strategy,
+            used: None,
+            bytes: 0,
+            error: None,
+        };
+
+        if strategy == SyncStrategy::Skip {
+            entry.used = Some(SyncStrategy::Skip);
+        } else if item.exists {
+            // Already present (e.g. tracked on this branch); leave it alone
+        } else if let Err(e) = dest_path.parent().map_or(Ok(()), std::fs::create_dir_all) {
+            entry.error = Some(e.to_string());
+        } else if item.is_dir && strategy == SyncStrategy::Symlink {
+            #[cfg(unix)]
+            match std::os::unix::fs::symlink(src_path, dest_path) {
+                Ok(()) => entry.used = Some(SyncStrategy::Symlink),
+                Err(e) => entry.error = Some(e.to_string()),
+            }
+            #[cfg(target_os = "windows")]
+            {
+                let dest_str = dest_path.display().to_string().replace("/", "\\");
+                let src_str = src_path.display().to_string().replace("/", "\\");
+                let cmd_str = format!("mklink /J \"{}\" \"{}\"", dest_str, src_str);
+
+                let output = create_command("cmd").arg("/C").arg(&cmd_str).output();
+                if output.map(|o| o.status.success()).unwrap_or(false) {
+                    entry.used = Some(SyncStrategy::Symlink);
+                } else {
+                    pending_admin_links.push((cmd_str, report.items.len()));
+                }
+            }
+        } else {
+            let result = if item.is_dir {
+                sync_tree(src_path, dest_path, strategy, cancel.as_ref())
+            } else {
+                sync_file(src_path, dest_path, strategy)
+            };
+            match result {
+                Ok(synced) => {
+                    entry.used = Some(synced.used);
+                    entry.bytes = synced.bytes;
+                }
+                Err(e) => entry.error = Some(e.to_string()),
+            }
+        }
+
+        report.items.push(entry);
+    }
+
+    #[cfg(target_os = "windows")]
+    if !pending_admin_links.is_empty() {
+        run_elevated_links(&pending_admin_links);
+        // The elevated shell gives no per-command result, so check what actually got created
+        for (_, idx) in &pending_admin_links {
+            let entry = &mut report.items[*idx];
+            if Path::new(&entry.destination).symlink_metadata().is_ok() {
+                entry.used = Some(SyncStrategy::Symlink);
+            } else {
+                entry.error = Some("Junction was not created (elevation denied or failed)".to_string());
+            }
+        }
+    }
+}
+
+// Batch Execute Admin Links
+#[cfg(target_os = "windows")]
+fn run_elevated_links(pending_admin_links: &[(String, usize)]) {
+    println!("requesting admin for {} items...", pending_admin_links.len());
+
+    // Use PowerShell script instead of Batch to handle Encoding/Unicode correctly.
+    // We prepend the UTF-8 BYTE ORDER MARK (BOM) so PowerShell explicitly knows it's UTF-8.
+    let mut ps1_content = String::from("\u{FEFF}");
+    ps1_content.push_str("$ErrorActionPreference = 'Stop'\n");
+
+    for (cmd, _) in pending_admin_links {
+        // cmd contains: mklink /J "dest" "src"
+        // In PowerShell, we run this via cmd /c.
+        // We need to escape quotes if necessary, but usually single quotes around the whole string works best in PS.
+        // Example: cmd /c 'mklink /J "dest" "src"'
+
+        // However, our cmd string already has quotes.
+        // Let's rely on PS parsing.
+        // cmd /c mklink /J "D:\..." "D:\..."
+        // In PS script: cmd /c $cmd  -- wait, $cmd needs to be exact.
+
+        // outputting: cmd /c "mklink /J \"dest\" \"src\""
+
+        // Simplest way: Write exact command line.
+        // cmd /c $cmd
+
+        ps1_content.push_str(&format!("cmd /c '{}'\n", cmd));
+    }
+
+    // ps1_content.push_str("Write-Host 'Press Key to exit...'\n");
+    // ps1_content.push_str("$null = $Host.UI.RawUI.ReadKey('NoEcho,IncludeKeyDown')\n");
+
+    let temp_dir = std::env::temp_dir();
+    let ps1_path = temp_dir.join("git_worktree_links.ps1");
+
+    if std::fs::write(&ps1_path, ps1_content).is_ok() {
+        let ps1_path_str = ps1_path.display().to_string();
+
+        // Run PowerShell as Admin, executing the generated script SILENTLY
+        // 1. We use create_command("powershell") which creates a hidden process for the "launcher" shell.
+        // 2. We use Start-Process with -WindowStyle Hidden so the ELEVATED shell is also hidden.
+        let _ = create_command("powershell")
+            .arg("-Command")
+            .arg(format!("Start-Process powershell -Verb RunAs -WindowStyle Hidden -ArgumentList '-ExecutionPolicy Bypass -NoProfile -WindowStyle Hidden -File \"{}\"' -Wait", ps1_path_str))
+            .output();
+    }
+}
@@ -0,0 +1,84 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::backend::{self, DiffStats};
+use crate::error::AppError;
+use crate::run_blocking;
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct AheadBehind {
+    pub ahead: u32,
+    pub behind: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub subject: String,
+    pub author: String,
+    pub timestamp: i64, // Unix seconds
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct WorktreeStatus {
+    pub path: String,
+    pub staged: u32,
+    pub unstaged: u32,
+    pub untracked: u32,
+    pub conflicted: u32,
+    pub upstream: Option<String>,
+    pub upstream_ahead_behind: Option<AheadBehind>,
+    pub base: Option<String>,
+    pub base_ahead_behind: Option<AheadBehind>,
+    pub last_commit: Option<CommitInfo>,
+    // Uncommitted changes to tracked files
+    pub diff: Option<DiffStats>,
+    // Set when the worktree could not be inspected (e.g. its directory is gone)
+    pub error: Option<String>,
+}
+
+// Status of one worktree from the selected backend, plus its uncommitted line
+// counts. Failures are reported in `error` rather than failing the whole list.
+pub fn collect_status(path: &str, base: Option<&str>) -> WorktreeStatus {
+    let backend = backend::current();
+    let mut status = match backend.status(Path::new(path), base) {
+        Ok(status) => status,
+        Err(e) => {
+            return WorktreeStatus {
+                path: path.to_string(),
+                base: base.map(|b| b.to_string()),
+                error: Some(e.to_string()),
+                ..Default::default()
+            }
+        }
+    };
+    // Fails on a branch without commits, which simply has no diff to show
+    status.diff = backend.diff_stats(Path::new(path), None).ok();
+    status
+}
+
+#[tauri::command]
+pub async fn worktree_status(project_path: String, base: Option<String>) -> Result<Vec<WorktreeStatus>, AppError> {
+    let worktrees = run_blocking(move || backend::current().list_worktrees(Path::new(&project_path))).await?;
+    let base = base.filter(|b| !b.is_empty());
+
+    // One blocking task per worktree, all running in parallel (within the git runner's limit)
+    let handles: Vec<_> = worktrees
+        .into_iter()
+        .filter(|wt| !wt.is_bare)
+        .map(|wt| {
+            let (path, base) = (wt.path.clone(), base.clone());
+            (wt.path, tauri::async_runtime::spawn_blocking(move || collect_status(&path, base.as_deref())))
+        })
+        .collect();
+
+    let mut statuses = Vec::with_capacity(handles.len());
+    for (path, handle) in handles {
+        statuses.push(handle.await.unwrap_or_else(|_| WorktreeStatus {
+            path,
+            error: Some("Status worker panicked".to_string()),
+            ..Default::default()
+        }));
+    }
+    Ok(statuses)
+}
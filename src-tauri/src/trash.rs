@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::AppError;
+use crate::git::{self, Git};
+use crate::{manager_dir, run_blocking, run_git};
+
+const TRASH_REF_PREFIX: &str = "refs/worktree-manager/trash/";
+const DEFAULT_MAX_AGE_DAYS: u64 = 30;
+
+// Everything needed to bring a removed worktree back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub worktree_path: String,
+    pub branch: Option<String>,
+    // Commit the branch (or detached HEAD) pointed at when it was removed
+    pub tip: String,
+    // Commit holding the uncommitted changes, parented on `tip`
+    pub snapshot: Option<String>,
+    // Private ref keeping `snapshot` (or `tip`) alive through gc
+    pub trash_ref: String,
+    pub removed_at: u64, // Unix seconds
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn journal_path(project_path: &str) -> Result<PathBuf, AppError> {
+    Ok(manager_dir(project_path)?.join("trash.json"))
+}
+
+fn load_journal(project_path: &str) -> Result<Vec<TrashEntry>, AppError> {
+    let path = journal_path(project_path)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&content).map_err(|e| AppError::InvalidFile { path: path.display().to_string(), reason: e.to_string() })
+}
+
+fn save_journal(project_path: &str, entries: &[TrashEntry]) -> Result<(), AppError> {
+    let path = journal_path(project_path)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(entries).map_err(|e| AppError::Other(e.to_string()))?;
+    std::fs::write(&path, content).map_err(|e| AppError::io(format!("Failed to write '{}'", path.display()), e))
+}
+
+// Ref-safe id from the worktree folder name, made unique with the removal time.
+fn make_id(worktree_path: &str, removed_at: u64) -> String {
+    let name = Path::new(worktree_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    format!("{}-{}", name.trim_matches('-'), removed_at)
+}
+
+// Runs git against a throwaway index so the worktree's own index is left untouched.
+fn git_with_index(dir: &str, index: &Path, args: &[&str]) -> Result<String, AppError> {
+    let output = Git::new(dir)
+        .env("GIT_INDEX_FILE", index)
+        // Snapshots must work even when the user never configured an identity
+        .env("GIT_AUTHOR_NAME", "Git Worktree Manager")
+        .env("GIT_AUTHOR_EMAIL", "worktree-manager@localhost")
+        .env("GIT_COMMITTER_NAME", "Git Worktree Manager")
+        .env("GIT_COMMITTER_EMAIL", "worktree-manager@localhost")
+        .args(args)
+        .run_blocking()?;
+    Ok(output.trim().to_string())
+}
+
+// Commits tracked and untracked (but not ignored) changes into a dangling commit on top of `tip`.
+fn snapshot_changes(worktree_path: &str, tip: &str, id: &str) -> Result<Option<String>, AppError> {
+    let status = run_git(worktree_path, &["status", "--porcelain", "-z"])?;
+    if status.is_empty() {
+        return Ok(None);
+    }
+
+    // Start from a copy of the real index so unchanged files keep their stat cache
+    let index = run_git(worktree_path, &["rev-parse", "--path-format=absolute", "--git-path", "index"])?;
+    let tmp_index = std::env::temp_dir().join(format!("gwm-trash-{}.index", id));
+    let _ = std::fs::copy(index.trim(), &tmp_index);
+
+    let result = (|| {
+        git_with_index(worktree_path, &tmp_index, &["add", "-A"])?;
+        let tree = git_with_index(worktree_path, &tmp_index, &["write-tree"])?;
+        let message = format!("worktree-manager snapshot of {}", worktree_path);
+        git_with_index(worktree_path, &tmp_index, &["commit-tree", &tree, "-p", tip, "-m", &message])
+    })();
+
+    let _ = std::fs::remove_file(&tmp_index);
+    result.map(Some)
+}
+
+// Records the branch tip and snapshots uncommitted work before a worktree is removed.
+// Returns None when there is nothing to record (no branch and no checkout left).
+pub fn record_removal(project_path: &str, worktree_path: &str, branch: Option<&str>) -> Result<Option<TrashEntry>, AppError> {
+    let branch = branch.filter(|b| !b.is_empty());
+    let worktree_exists = Path::new(worktree_path).exists();
+
+    let tip = match branch {
+        Some(b) => run_git(project_path, &["rev-parse", "--verify", &format!("refs/heads/{}^{{commit}}", b)]).ok(),
+        None if worktree_exists => run_git(worktree_path, &["rev-parse", "--verify", "HEAD"]).ok(),
+        None => None,
+    };
+    let Some(tip) = tip.map(|t| t.trim().to_string()) else {
+        return Ok(None);
+    };
+
+    let removed_at = now_secs();
+    let id = make_id(worktree_path, removed_at);
+    let snapshot = if worktree_exists {
+        snapshot_changes(worktree_path, &tip, &id)?
+    } else {
+        None
+    };
+
+    let trash_ref = format!("{}{}", TRASH_REF_PREFIX, id);
+    run_git(project_path, &["update-ref", &trash_ref, snapshot.as_deref().unwrap_or(&tip)])?;
+
+    let entry = TrashEntry {
+        id,
+        worktree_path: worktree_path.to_string(),
+        branch: branch.map(|b| b.to_string()),
+        tip,
+        snapshot,
+        trash_ref,
+        removed_at,
+    };
+
+    let mut journal = load_journal(project_path)?;
+    journal.push(entry.clone());
+    save_journal(project_path, &journal)?;
+    Ok(Some(entry))
+}
+
+// Forgets an entry recorded for a removal that did not go ahead.
+pub fn discard(project_path: &str, entry: &TrashEntry) -> Result<(), AppError> {
+    let _ = run_git(project_path, &["update-ref", "-d", &entry.trash_ref]);
+    let mut journal = load_journal(project_path)?;
+    journal.retain(|e| e.id != entry.id);
+    save_journal(project_path, &journal)
+}
+
+#[tauri::command]
+pub async fn list_trash(project_path: String) -> Result<Vec<TrashEntry>, AppError> {
+    run_blocking(move || {
+        let mut entries = load_journal(&project_path)?;
+        entries.sort_by_key(|e| std::cmp::Reverse(e.removed_at));
+        Ok(entries)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn restore_worktree(project_path: String, id: String) -> Result<(), AppError> {
+    run_blocking(move || restore(project_path, id)).await
+}
+
+fn restore(project_path: String, id: String) -> Result<(), AppError> {
+    let mut journal = load_journal(&project_path)?;
+    let pos = journal
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("No removed worktree '{}' in trash", id)))?;
+    let entry = journal[pos].clone();
+
+    if Path::new(&entry.worktree_path).exists() {
+        return Err(AppError::PathExists { path: entry.worktree_path.clone(), git: None });
+    }
+
+    // 1. Recreate the branch at its recorded tip (or reuse it if it still points there)
+    let mut created_branch = None;
+    let added = match &entry.branch {
+        Some(branch) => {
+            let existing = run_git(&project_path, &["rev-parse", "--verify", &format!("refs/heads/{}", branch)]);
+            match existing {
+                Ok(sha) if sha.trim() != entry.tip => {
+                    return Err(AppError::BranchMoved { branch: branch.clone() });
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    run_git(&project_path, &["branch", branch, &entry.tip])?;
+                    created_branch = Some(branch.as_str());
+                }
+            }
+            checkout(&project_path, &["worktree", "add", &entry.worktree_path, branch])
+        }
+        None => checkout(&project_path, &["worktree", "add", "--detach", &entry.worktree_path, &entry.tip]),
+    };
+
+    // 2. Put the uncommitted changes back as unstaged edits
+    let restored = added.and_then(|_| match &entry.snapshot {
+        Some(snapshot) => checkout(&entry.worktree_path, &["read-tree", "--reset", "-u", snapshot])
+            .and_then(|_| run_git(&entry.worktree_path, &["reset", "-q"])),
+        None => Ok(String::new()),
+    });
+    if let Err(e) = restored {
+        // Take the half-restored worktree away again so restoring can be retried;
+        // the entry and its ref stay in the trash
+        let _ = checkout(&project_path, &["worktree", "remove", "--force", &entry.worktree_path]);
+        if Path::new(&entry.worktree_path).exists() {
+            let _ = std::fs::remove_dir_all(&entry.worktree_path);
+        }
+        let _ = run_git(&project_path, &["worktree", "prune"]);
+        if let Some(branch) = created_branch {
+            let _ = run_git(&project_path, &["branch", "-D", branch]);
+        }
+        return Err(e);
+    }
+
+    // Everything is back; only now can the snapshot go
+    let _ = run_git(&project_path, &["update-ref", "-d", &entry.trash_ref]);
+    journal.remove(pos);
+    save_journal(&project_path, &journal)
+}
+
+// Runs a git command that writes a whole worktree, with the timeout that needs.
+fn checkout(dir: &str, args: &[&str]) -> Result<String, AppError> {
+    Git::new(dir).args(args).timeout(git::CHECKOUT_TIMEOUT).run_blocking()
+}
+
+// Drops trash entries older than `max_age_days` (default 30) along with their refs.
+#[tauri::command]
+pub async fn purge_trash(project_path: String, max_age_days: Option<u64>) -> Result<usize, AppError> {
+    run_blocking(move || purge(project_path, max_age_days)).await
+}
+
+fn purge(project_path: String, max_age_days: Option<u64>) -> Result<usize, AppError> {
+    let max_age = max_age_days.unwrap_or(DEFAULT_MAX_AGE_DAYS) * 24 * 60 * 60;
+    let cutoff = now_secs().saturating_sub(max_age);
+
+    let journal = load_journal(&project_path)?;
+    let (expired, kept): (Vec<_>, Vec<_>) = journal.into_iter().partition(|e| e.removed_at <= cutoff);
+
+    for entry in &expired {
+        let _ = run_git(&project_path, &["update-ref", "-d", &entry.trash_ref]);
+    }
+    save_journal(&project_path, &kept)?;
+    Ok(expired.len())
+}
@@ -0,0 +1,121 @@
+use std::fs::FileType;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+// Shared flag a long-running operation checks to stop early.
+pub type CancelFlag = Arc<AtomicBool>;
+
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    // Worker threads; 0 uses every core
+    pub threads: usize,
+    pub cancel: Option<CancelFlag>,
+    // Skip directories that cannot be read instead of failing the walk
+    pub skip_unreadable: bool,
+}
+
+pub struct WalkEntry {
+    pub path: PathBuf,
+    // Type of the entry itself; symlinks and junctions are never followed
+    pub file_type: FileType,
+}
+
+// Directories waiting to be read, and how many are queued or being read.
+struct Queue {
+    dirs: Vec<PathBuf>,
+    pending: usize,
+}
+
+pub fn cancelled_error() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Interrupted, "Cancelled")
+}
+
+// Walks everything below `root` on several threads, calling `visit` once per
+// entry (in no particular order). A directory's entry is always visited before
+// anything inside it. The first error, or cancellation, stops the walk and is
+// returned.
+pub fn walk<F>(root: &Path, options: &WalkOptions, visit: F) -> std::io::Result<()>
+where
+    F: Fn(&WalkEntry) -> std::io::Result<()> + Sync,
+{
+    let threads = match options.threads {
+        0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4),
+        n => n,
+    };
+
+    let queue = Mutex::new(Queue { dirs: vec![root.to_path_buf()], pending: 1 });
+    let ready = Condvar::new();
+    let failure: Mutex<Option<std::io::Error>> = Mutex::new(None);
+    let stopped = AtomicBool::new(false);
+
+    let is_cancelled = || options.cancel.as_ref().is_some_and(|c| c.load(Ordering::Relaxed));
+    let fail = |e: std::io::Error| {
+        let mut failure = failure.lock().unwrap();
+        if failure.is_none() {
+            *failure = Some(e);
+        }
+        stopped.store(true, Ordering::Relaxed);
+    };
+
+    // Reads one directory, queueing its subdirectories
+    let read_dir = |dir: &Path| -> std::io::Result<()> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) if options.skip_unreadable => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            if stopped.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            if is_cancelled() {
+                return Err(cancelled_error());
+            }
+            let entry = entry?;
+            let walk_entry = WalkEntry { path: entry.path(), file_type: entry.file_type()? };
+            visit(&walk_entry)?;
+            if walk_entry.file_type.is_dir() {
+                let mut queue = queue.lock().unwrap();
+                queue.dirs.push(walk_entry.path);
+                queue.pending += 1;
+                ready.notify_one();
+            }
+        }
+        Ok(())
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let next = {
+                    let mut queue = queue.lock().unwrap();
+                    loop {
+                        if stopped.load(Ordering::Relaxed) || queue.pending == 0 {
+                            break None;
+                        }
+                        if let Some(dir) = queue.dirs.pop() {
+                            break Some(dir);
+                        }
+                        queue = ready.wait(queue).unwrap();
+                    }
+                };
+                let Some(dir) = next else {
+                    ready.notify_all();
+                    return;
+                };
+
+                if let Err(e) = read_dir(&dir) {
+                    fail(e);
+                }
+                queue.lock().unwrap().pending -= 1;
+                ready.notify_all();
+            });
+        }
+    });
+
+    match failure.into_inner().unwrap() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,197 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::error::AppError;
+use crate::status::{collect_status, WorktreeStatus};
+use crate::{backend, common_dir, run_blocking, Worktree};
+
+// Event pushing worktree list and status changes to the UI.
+const WORKTREE_EVENT: &str = "worktree-changed";
+
+// Quiet time that ends a burst of changes (a checkout writes HEAD, index and refs)
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+// Longest a refresh waits while changes keep coming
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorktreeChanged {
+    pub project_path: String,
+    pub worktrees: Vec<Worktree>,
+    // Fresh status of the worktrees the change touched (all of them when refs moved)
+    pub statuses: Vec<WorktreeStatus>,
+}
+
+// The repository being watched. Dropping it stops the watcher and its thread.
+struct RepoWatch {
+    project_path: String,
+    base: Arc<Mutex<Option<String>>>,
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+}
+
+#[derive(Default)]
+pub struct Watchers(Mutex<Option<RepoWatch>>);
+
+// What a batch of file system events means for the worktrees.
+#[derive(Default)]
+struct Change {
+    // Refs moved or worktrees came and went: every status may differ
+    all: bool,
+    main: bool,
+    // Admin folder names (under .git/worktrees) of linked worktrees whose HEAD or index changed
+    linked: HashSet<String>,
+}
+
+impl Change {
+    fn is_empty(&self) -> bool {
+        !self.all && !self.main && self.linked.is_empty()
+    }
+
+    fn add(&mut self, common_dir: &Path, event: notify::Result<Event>) {
+        let Ok(event) = event else {
+            return;
+        };
+        // Git reading its own files (our status queries included) is not a change
+        if event.kind.is_access() {
+            return;
+        }
+        for path in &event.paths {
+            let Ok(relative) = path.strip_prefix(common_dir) else {
+                continue;
+            };
+            let parts: Vec<&str> = relative.iter().filter_map(|p| p.to_str()).collect();
+            match parts.as_slice() {
+                ["HEAD"] | ["index"] => self.main = true,
+                ["packed-refs"] | ["worktrees"] | ["worktrees", _] => self.all = true,
+                ["refs", .., name] if !name.ends_with(".lock") => self.all = true,
+                ["worktrees", id, "HEAD" | "index" | "locked" | "gitdir"] => {
+                    self.linked.insert(id.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// Starts pushing `worktree-changed` events for the repository of `project_path`,
+// replacing the previous watch. Called again for the same repository, it only
+// updates the base branch statuses are compared with.
+#[tauri::command]
+pub async fn watch_worktrees(app: AppHandle, state: State<'_, Watchers>, project_path: String, base: Option<String>) -> Result<(), AppError> {
+    let base = base.filter(|b| !b.is_empty());
+    {
+        let current = state.0.lock().map_err(|_| AppError::Other("Failed to lock watcher state".to_string()))?;
+        if let Some(watch) = current.as_ref().filter(|w| w.project_path == project_path) {
+            *watch.base.lock().unwrap_or_else(|e| e.into_inner()) = base;
+            return Ok(());
+        }
+    }
+
+    let path = project_path.clone();
+    let common_dir = run_blocking(move || common_dir(&path)).await?;
+    let watch = start(app, project_path, common_dir, base)?;
+    *state.0.lock().map_err(|_| AppError::Other("Failed to lock watcher state".to_string()))? = Some(watch);
+    Ok(())
+}
+
+fn watch_error(path: &Path, e: notify::Error) -> AppError {
+    AppError::Other(format!("Failed to watch '{}': {}", path.display(), e))
+}
+
+fn start(app: AppHandle, project_path: String, common_dir: PathBuf, base: Option<String>) -> Result<RepoWatch, AppError> {
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| watch_error(&common_dir, e))?;
+    // HEAD, index and packed-refs of the main worktree; not objects/ or logs/
+    watcher.watch(&common_dir, RecursiveMode::NonRecursive).map_err(|e| watch_error(&common_dir, e))?;
+    let refs = common_dir.join("refs");
+    watcher.watch(&refs, RecursiveMode::Recursive).map_err(|e| watch_error(&refs, e))?;
+
+    let watcher = Arc::new(Mutex::new(watcher));
+    let base = Arc::new(Mutex::new(base));
+    let watch = RepoWatch { project_path: project_path.clone(), base: base.clone(), _watcher: watcher.clone() };
+    let watcher = Arc::downgrade(&watcher);
+    std::thread::spawn(move || run(app, project_path, common_dir, base, watcher, rx));
+    Ok(watch)
+}
+
+// Watches .git/worktrees, which git creates with the first linked worktree and
+// removes with the last. Returns whether it is watched now.
+fn watch_linked(watcher: &Weak<Mutex<RecommendedWatcher>>, worktrees_dir: &Path, watching: bool) -> bool {
+    let Some(watcher) = watcher.upgrade() else {
+        return watching;
+    };
+    let mut watcher = watcher.lock().unwrap_or_else(|e| e.into_inner());
+    match (worktrees_dir.is_dir(), watching) {
+        (true, false) => watcher.watch(worktrees_dir, RecursiveMode::Recursive).is_ok(),
+        (false, true) => {
+            let _ = watcher.unwatch(worktrees_dir);
+            false
+        }
+        (_, watching) => watching,
+    }
+}
+
+// Worktree checked out from the admin folder .git/worktrees/<id>: its gitdir file holds "<path>/.git".
+fn linked_path(common_dir: &Path, id: &str) -> Option<String> {
+    let gitdir = std::fs::read_to_string(common_dir.join("worktrees").join(id).join("gitdir")).ok()?;
+    let path = Path::new(gitdir.trim()).parent()?;
+    Some(path.to_string_lossy().to_string())
+}
+
+fn run(
+    app: AppHandle,
+    project_path: String,
+    common_dir: PathBuf,
+    base: Arc<Mutex<Option<String>>>,
+    watcher: Weak<Mutex<RecommendedWatcher>>,
+    rx: Receiver<notify::Result<Event>>,
+) {
+    let worktrees_dir = common_dir.join("worktrees");
+    let mut watching_linked = watch_linked(&watcher, &worktrees_dir, false);
+
+    // Ends when the watcher is dropped, which closes the channel
+    while let Ok(event) = rx.recv() {
+        let mut change = Change::default();
+        change.add(&common_dir, event);
+        let started = Instant::now();
+        while started.elapsed() < MAX_DELAY {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(event) => change.add(&common_dir, event),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        if change.is_empty() {
+            continue;
+        }
+        watching_linked = watch_linked(&watcher, &worktrees_dir, watching_linked);
+
+        let worktrees = match backend::current().list_worktrees(Path::new(&project_path)) {
+            Ok(worktrees) => worktrees,
+            Err(e) => {
+                println!("Failed to list worktrees of {}: {}", project_path, e);
+                continue;
+            }
+        };
+        let paths: Vec<String> = if change.all {
+            worktrees.iter().filter(|wt| !wt.is_bare).map(|wt| wt.path.clone()).collect()
+        } else {
+            let main = worktrees.first().filter(|wt| change.main && !wt.is_bare).map(|wt| wt.path.clone());
+            main.into_iter().chain(change.linked.iter().filter_map(|id| linked_path(&common_dir, id))).collect()
+        };
+
+        let base = base.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let statuses = std::thread::scope(|scope| {
+            let handles: Vec<_> = paths.iter().map(|path| scope.spawn(|| collect_status(path, base.as_deref()))).collect();
+            handles.into_iter().filter_map(|h| h.join().ok()).collect()
+        });
+
+        let _ = app.emit(WORKTREE_EVENT, WorktreeChanged { project_path: project_path.clone(), worktrees, statuses });
+    }
+}
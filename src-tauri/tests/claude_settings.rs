@@ -0,0 +1,129 @@
+// Merging gwm-hook into a Claude settings.json: our entries are added or
+// refreshed, everything else in the file survives, and a file we cannot read
+// as settings is left exactly as it was.
+//
+//     cargo test --test claude_settings
+
+mod common;
+
+use app_lib::claude_settings::{install_hooks, HOOK_EVENTS};
+use common::TempDir;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+const CMD: &str = "\"/opt/gwm/gwm-hook\" --token new";
+
+fn settings(dir: &TempDir) -> PathBuf {
+    dir.path().join("settings.json")
+}
+
+fn read(path: &Path) -> Value {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn backups(dir: &TempDir) -> Vec<PathBuf> {
+    std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.to_string_lossy().ends_with(".bak"))
+        .collect()
+}
+
+// Every command registered for `event`, across its groups.
+fn commands(settings: &Value, event: &str) -> Vec<String> {
+    settings["hooks"][event]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|group| group["hooks"].as_array().unwrap())
+        .map(|hook| hook["command"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn a_second_run_changes_nothing() {
+    let dir = TempDir::new("idempotent");
+    let path = settings(&dir);
+
+    assert!(install_hooks(&path, CMD).unwrap());
+    let first = std::fs::read_to_string(&path).unwrap();
+    for event in HOOK_EVENTS {
+        assert_eq!(commands(&read(&path), event), vec![format!("{} {}", CMD, event)]);
+    }
+
+    assert!(!install_hooks(&path, CMD).unwrap());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), first);
+    // Nothing to back up the first time (no file) or the second (no change)
+    assert!(backups(&dir).is_empty());
+}
+
+#[test]
+fn foreign_hooks_and_settings_survive() {
+    let dir = TempDir::new("foreign");
+    let path = settings(&dir);
+    let original = json!({
+        "model": "opus",
+        "hooks": {
+            "Stop": [{ "hooks": [{ "type": "command", "command": "notify-send done" }] }],
+            "PreToolUse": [{ "matcher": "Bash", "hooks": [{ "type": "command", "command": "audit.sh" }] }],
+            "SessionStart": [{ "hooks": [{ "type": "command", "command": "hello.sh" }] }]
+        }
+    });
+    std::fs::write(&path, serde_json::to_string_pretty(&original).unwrap()).unwrap();
+
+    assert!(install_hooks(&path, CMD).unwrap());
+    let merged = read(&path);
+    assert_eq!(merged["model"], "opus");
+    assert_eq!(merged["hooks"]["SessionStart"], original["hooks"]["SessionStart"]);
+    assert_eq!(commands(&merged, "Stop"), vec!["notify-send done".to_string(), format!("{} Stop", CMD)]);
+    // The user's group keeps its matcher; ours gets a group of its own
+    assert_eq!(merged["hooks"]["PreToolUse"][0], original["hooks"]["PreToolUse"][0]);
+    assert_eq!(commands(&merged, "PreToolUse"), vec!["audit.sh".to_string(), format!("{} PreToolUse", CMD)]);
+
+    // The file before the change is kept next to it
+    let backups = backups(&dir);
+    assert_eq!(backups.len(), 1);
+    assert_eq!(read(&backups[0]), original);
+}
+
+#[test]
+fn stale_entries_of_ours_are_replaced() {
+    let dir = TempDir::new("stale");
+    let path = settings(&dir);
+    let ps1 = "powershell -ExecutionPolicy Bypass -File \"C:\\Users\\me\\.claude\\hooks\\git-worktree-hook.ps1\" Stop";
+    std::fs::write(
+        &path,
+        json!({
+            "hooks": {
+                "Stop": [
+                    { "hooks": [{ "type": "command", "command": ps1 }] },
+                    { "hooks": [{ "type": "command", "command": "notify-send done" }, { "type": "command", "command": "\"/old/gwm-hook\" --token old Stop" }] }
+                ],
+                "PermissionRequest": [{ "hooks": [{ "type": "command", "command": "\"/old/gwm-hook\" --token old PermissionRequest" }] }]
+            }
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    assert!(install_hooks(&path, CMD).unwrap());
+    let merged = read(&path);
+    assert_eq!(commands(&merged, "Stop"), vec!["notify-send done".to_string(), format!("{} Stop", CMD)]);
+    assert_eq!(commands(&merged, "PermissionRequest"), vec![format!("{} PermissionRequest", CMD)]);
+    // The group that only held the PowerShell entry is gone, the shared one stays
+    assert_eq!(merged["hooks"]["Stop"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn invalid_settings_are_left_untouched() {
+    let dir = TempDir::new("invalid");
+    let path = settings(&dir);
+
+    for content in ["{ \"hooks\": ", "[1, 2]", "{ \"hooks\": [] }", "{ \"hooks\": { \"Stop\": {} } }"] {
+        std::fs::write(&path, content).unwrap();
+        let err = install_hooks(&path, CMD).unwrap_err();
+        assert!(err.to_string().contains("not valid"), "{}: {}", content, err);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+    }
+    assert!(backups(&dir).is_empty());
+}
@@ -0,0 +1,243 @@
+// Conformance suite for the git backends: every query runs against the same
+// temporary repositories through the CLI and libgit2 backends, and both must
+// return identical results (compared as the JSON the UI receives).
+//
+//     cargo test --test git_backend
+//
+// Needs `git` on the PATH to build the fixtures.
+
+mod common;
+
+use app_lib::backend::{CliBackend, GitBackend, Libgit2Backend};
+use common::{git, try_git, write, TempDir};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+const BACKENDS: [&dyn GitBackend; 2] = [&CliBackend, &Libgit2Backend];
+
+fn commit(dir: &Path, file: &str, content: &str, message: &str) {
+    write(&dir.join(file), content);
+    git(dir, &["add", file]);
+    git(dir, &["commit", "-q", "-m", message]);
+}
+
+// Runs `query` with every backend and checks they all agree. Returns the common answer.
+fn same<T: Serialize, E: Serialize>(what: &str, query: impl Fn(&dyn GitBackend) -> Result<T, E>) -> Value {
+    let results: Vec<(&str, Value)> = BACKENDS
+        .iter()
+        .map(|backend| {
+            let result = match query(*backend) {
+                Ok(value) => serde_json::json!({ "ok": value }),
+                // Only the kind: the message is each implementation's own
+                Err(e) => serde_json::json!({ "err": serde_json::to_value(e).unwrap()["kind"] }),
+            };
+            (backend.name(), result)
+        })
+        .collect();
+    for (name, result) in &results[1..] {
+        assert_eq!(result, &results[0].1, "{}: {} and {} disagree", what, name, results[0].0);
+    }
+    results[0].1.clone()
+}
+
+// Main checkout on `main` with two commits, a tag of each kind, a `feature`
+// branch and an `origin` remote that `main` tracks.
+fn repository(root: &Path) -> PathBuf {
+    let remote = root.join("remote.git");
+    let repo = root.join("repo");
+    std::fs::create_dir_all(&repo).unwrap();
+    git(root, &["init", "-q", "--bare", "-b", "main", remote.to_str().unwrap()]);
+    git(&repo, &["init", "-q", "-b", "main"]);
+    commit(&repo, "README.md", "hello\n", "first");
+    git(&repo, &["tag", "v1"]);
+    commit(&repo, "src/lib.rs", "fn a() {}\nfn b() {}\n", "second\n\nwith a body");
+    git(&repo, &["tag", "-a", "v2", "-m", "release"]);
+    git(&repo, &["branch", "feature"]);
+    git(&repo, &["remote", "add", "origin", remote.to_str().unwrap()]);
+    git(&repo, &["push", "-q", "-u", "origin", "main", "feature"]);
+    git(&repo, &["remote", "set-head", "origin", "main"]);
+    // One local commit ahead of origin/main
+    commit(&repo, "notes.txt", "ahead\n", "third");
+    repo
+}
+
+#[test]
+fn list_worktrees() {
+    let tmp = TempDir::new("list");
+    let repo = repository(tmp.path());
+    let wt = |name: &str| tmp.path().join(name).to_str().unwrap().to_string();
+
+    git(&repo, &["worktree", "add", "-q", &wt("feature"), "feature"]);
+    git(&repo, &["worktree", "add", "-q", "--detach", &wt("detached"), "v1"]);
+    git(&repo, &["worktree", "add", "-q", "-b", "locked", &wt("locked")]);
+    git(&repo, &["worktree", "lock", "--reason", "on a USB drive", &wt("locked")]);
+    git(&repo, &["worktree", "add", "-q", "-b", "locked-silently", &wt("locked-silently")]);
+    git(&repo, &["worktree", "lock", &wt("locked-silently")]);
+    git(&repo, &["worktree", "add", "-q", "-b", "gone", &wt("gone")]);
+    std::fs::remove_dir_all(wt("gone")).unwrap();
+
+    // From the main checkout, a linked worktree and a subdirectory alike
+    let from_main = same("list_worktrees", |b| b.list_worktrees(&repo));
+    assert_eq!(same("list_worktrees from a worktree", |b| b.list_worktrees(Path::new(&wt("feature")))), from_main);
+    assert_eq!(same("list_worktrees from a subdirectory", |b| b.list_worktrees(&repo.join("src"))), from_main);
+
+    let list = from_main["ok"].as_array().unwrap();
+    let paths: Vec<&str> = list.iter().map(|w| w["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, [repo.to_str().unwrap(), &wt("detached"), &wt("feature"), &wt("gone"), &wt("locked"), &wt("locked-silently")]);
+    assert_eq!(list[0]["branch"], "main");
+    assert_eq!(list[0]["head_hash"], git(&repo, &["rev-parse", "HEAD"]));
+    assert_eq!(list[1]["is_detached"], true);
+    assert_eq!(list[1]["head_hash"], git(&repo, &["rev-parse", "v1"]));
+    assert_eq!(list[2]["branch"], "feature");
+    assert_eq!(list[3]["is_prunable"], true);
+    assert_eq!(list[4]["lock_reason"], "on a USB drive");
+    assert_eq!(list[5]["is_locked"], true);
+    assert_eq!(list[5]["lock_reason"], Value::Null);
+}
+
+#[test]
+fn list_worktrees_of_bare_and_empty_repositories() {
+    let tmp = TempDir::new("bare");
+    let repo = repository(tmp.path());
+    let bare = tmp.path().join("bare.git");
+    git(tmp.path(), &["clone", "-q", "--bare", repo.to_str().unwrap(), bare.to_str().unwrap()]);
+    git(&bare, &["worktree", "add", "-q", tmp.path().join("from-bare").to_str().unwrap(), "feature"]);
+    let list = same("list_worktrees of a bare repository", |b| b.list_worktrees(&bare));
+    assert_eq!(list["ok"][0]["is_bare"], true);
+    assert_eq!(list["ok"][1]["branch"], "feature");
+
+    // No commit yet: HEAD is all zeros but the branch is known
+    let empty = tmp.path().join("empty");
+    std::fs::create_dir_all(&empty).unwrap();
+    git(&empty, &["init", "-q", "-b", "trunk"]);
+    let list = same("list_worktrees of an empty repository", |b| b.list_worktrees(&empty));
+    assert_eq!(list["ok"][0]["branch"], "trunk");
+    assert_eq!(list["ok"][0]["head_hash"], "0".repeat(40));
+}
+
+#[test]
+fn status() {
+    let tmp = TempDir::new("status");
+    let repo = repository(tmp.path());
+
+    // Clean, one commit ahead of its upstream and two ahead of v1
+    let clean = same("status of a clean worktree", |b| b.status(&repo, Some("v1")));
+    assert_eq!(clean["ok"]["upstream"], "origin/main");
+    assert_eq!(clean["ok"]["upstream_ahead_behind"], serde_json::json!({ "ahead": 1, "behind": 0 }));
+    assert_eq!(clean["ok"]["base_ahead_behind"], serde_json::json!({ "ahead": 2, "behind": 0 }));
+    assert_eq!(clean["ok"]["last_commit"]["subject"], "third");
+    assert_eq!(clean["ok"]["last_commit"]["author"], "Tester");
+
+    // A merge conflict in a linked worktree, behind `main`
+    let conflict = tmp.path().join("conflict");
+    git(&repo, &["worktree", "add", "-q", "-b", "topic", conflict.to_str().unwrap(), "v1"]);
+    commit(&conflict, "README.md", "topic\n", "topic change");
+    commit(&repo, "README.md", "main\n", "main change");
+    assert!(!try_git(&conflict, &["merge", "-q", "main"]).status.success(), "the merge should conflict");
+    let conflicted = same("status with a conflict", |b| b.status(&conflict, Some("main")));
+    assert_eq!(conflicted["ok"]["conflicted"], 1);
+    assert_eq!(conflicted["ok"]["upstream"], Value::Null);
+    assert_eq!(conflicted["ok"]["base_ahead_behind"]["behind"], 3);
+
+    // Staged (including a rename), modified, both, untracked files and an untracked folder
+    git(&repo, &["mv", "notes.txt", "renamed.txt"]);
+    write(&repo.join("staged.txt"), "new\n");
+    git(&repo, &["add", "staged.txt"]);
+    write(&repo.join("staged.txt"), "new, then edited\n");
+    write(&repo.join("README.md"), "changed\n");
+    write(&repo.join("untracked.txt"), "?\n");
+    write(&repo.join("build/out/a.o"), "?\n");
+    write(&repo.join("build/out/b.o"), "?\n");
+    let dirty = same("status of a dirty worktree", |b| b.status(&repo, Some("feature")));
+    assert_eq!(dirty["ok"]["staged"], 2);
+    assert_eq!(dirty["ok"]["unstaged"], 2);
+    assert_eq!(dirty["ok"]["untracked"], 2);
+    assert_eq!(dirty["ok"]["base_ahead_behind"], serde_json::json!({ "ahead": 2, "behind": 0 }));
+
+    // Upstream whose remote branch was deleted: named, but nothing to count against
+    git(&repo, &["push", "-q", "origin", "--delete", "feature"]);
+    git(&repo, &["fetch", "-q", "--prune", "origin"]);
+    let feature = tmp.path().join("feature");
+    git(&repo, &["worktree", "add", "-q", feature.to_str().unwrap(), "feature"]);
+    let gone = same("status with a gone upstream", |b| b.status(&feature, None));
+    assert_eq!(gone["ok"]["upstream"], "origin/feature");
+    assert_eq!(gone["ok"]["upstream_ahead_behind"], Value::Null);
+
+    // Unknown base and a branch without commits are not errors
+    same("status with an unknown base", |b| b.status(&repo, Some("no-such-branch")));
+    let empty = tmp.path().join("empty");
+    std::fs::create_dir_all(&empty).unwrap();
+    git(&empty, &["init", "-q", "-b", "main"]);
+    write(&empty.join("a.txt"), "a\n");
+    let unborn = same("status of an empty repository", |b| b.status(&empty, Some("main")));
+    assert_eq!(unborn["ok"]["untracked"], 1);
+    assert_eq!(unborn["ok"]["last_commit"], Value::Null);
+}
+
+#[test]
+fn branches_and_refs() {
+    let tmp = TempDir::new("refs");
+    let repo = repository(tmp.path());
+    git(&repo, &["branch", "--set-upstream-to=main", "feature"]);
+    write(&repo.join("stashed.txt"), "x\n");
+    git(&repo, &["stash", "-q", "--include-untracked"]);
+
+    let branches = same("branches", |b| b.branches(&repo));
+    let names: Vec<(&str, bool)> = branches["ok"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| (b["name"].as_str().unwrap(), b["is_remote"].as_bool().unwrap()))
+        .collect();
+    assert_eq!(names, [("feature", false), ("main", false), ("origin/feature", true), ("origin/main", true)]);
+    assert_eq!(branches["ok"][0]["upstream"], "main");
+    assert_eq!(branches["ok"][1]["upstream"], "origin/main");
+
+    let refs = same("refs", |b| b.refs(&repo));
+    let names: Vec<&str> = refs["ok"].as_array().unwrap().iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(
+        names,
+        ["refs/heads/feature", "refs/heads/main", "refs/remotes/origin/HEAD", "refs/remotes/origin/feature", "refs/remotes/origin/main", "refs/stash", "refs/tags/v1", "refs/tags/v2"]
+    );
+    // Annotated tags point at the tag object, not the commit
+    assert_eq!(refs["ok"][7]["target"], git(&repo, &["rev-parse", "v2"]));
+}
+
+#[test]
+fn diff_stats() {
+    let tmp = TempDir::new("diff");
+    let repo = repository(tmp.path());
+
+    let clean = same("diff_stats of a clean worktree", |b| b.diff_stats(&repo, None));
+    assert_eq!(clean["ok"], serde_json::json!({ "files_changed": 0, "insertions": 0, "deletions": 0 }));
+
+    // Staged and unstaged edits, a deletion, a rename and a binary file; untracked files do not count
+    write(&repo.join("src/lib.rs"), "fn a() {}\nfn c() {}\nfn d() {}\n");
+    git(&repo, &["add", "src/lib.rs"]);
+    write(&repo.join("README.md"), "hello\nworld\n");
+    git(&repo, &["rm", "-q", "notes.txt"]);
+    git(&repo, &["mv", "README.md", "READ.md"]);
+    std::fs::write(repo.join("image.bin"), [0u8, 1, 2, 0, 255]).unwrap();
+    git(&repo, &["add", "image.bin"]);
+    write(&repo.join("untracked.txt"), "not counted\n");
+    let dirty = same("diff_stats of a dirty worktree", |b| b.diff_stats(&repo, None));
+    assert_eq!(dirty["ok"]["files_changed"], 5);
+
+    same("diff_stats against a tag", |b| b.diff_stats(&repo, Some("v1")));
+    same("diff_stats against a branch", |b| b.diff_stats(&repo, Some("origin/main")));
+}
+
+#[test]
+fn errors() {
+    let tmp = TempDir::new("errors");
+    let repo = repository(tmp.path());
+    let outside = TempDir::new("not-a-repo");
+
+    let kind = |v: Value| v["err"].as_str().map(String::from);
+    assert_eq!(kind(same("list_worktrees outside a repository", |b| b.list_worktrees(outside.path()))).as_deref(), Some("not_a_repository"));
+    assert_eq!(kind(same("status outside a repository", |b| b.status(outside.path(), None))).as_deref(), Some("not_a_repository"));
+    assert_eq!(kind(same("branches outside a repository", |b| b.branches(outside.path()))).as_deref(), Some("not_a_repository"));
+    assert_eq!(kind(same("refs outside a repository", |b| b.refs(outside.path()))).as_deref(), Some("not_a_repository"));
+    assert_eq!(kind(same("diff_stats against an unknown ref", |b| b.diff_stats(&repo, Some("no-such-ref")))).as_deref(), Some("invalid_ref"));
+}
@@ -0,0 +1,136 @@
+// Runs the gwm-hook helper the way Claude does (event argument, hook input on
+// stdin) against a stand-in for the app's hook server, found through a
+// discovery file in a temporary home directory.
+//
+//     cargo test --test gwm_hook
+
+mod common;
+
+use common::TempDir;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::thread::JoinHandle;
+
+// Publishes `discovery` where the hook looks for the running app, under `home`.
+fn discover(home: &TempDir, discovery: Value) {
+    let hooks = home.path().join(".claude").join("hooks");
+    std::fs::create_dir_all(&hooks).unwrap();
+    std::fs::write(hooks.join("git-worktree-manager.json"), discovery.to_string()).unwrap();
+}
+
+// One request as the server received it.
+struct Received {
+    head: String,
+    body: Value,
+}
+
+// Answers a single request with `status` and hands it back when joined.
+fn serve_once<S: Read + Write + Send + 'static>(accept: impl FnOnce() -> S + Send + 'static, status: &'static str) -> JoinHandle<Received> {
+    std::thread::spawn(move || {
+        let mut stream = BufReader::new(accept());
+        let mut head = String::new();
+        loop {
+            let mut line = String::new();
+            stream.read_line(&mut line).unwrap();
+            if line == "\r\n" || line.is_empty() {
+                break;
+            }
+            head.push_str(&line);
+        }
+        let length: usize = head
+            .lines()
+            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length: ").map(|v| v.trim().parse().unwrap()))
+            .unwrap_or(0);
+        let mut body = vec![0; length];
+        stream.read_exact(&mut body).unwrap();
+        stream.get_mut().write_all(format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).as_bytes()).unwrap();
+        Received { head, body: serde_json::from_slice(&body).unwrap() }
+    })
+}
+
+fn run_hook(home: &Path, args: &[&str], input: &str) -> Output {
+    let home_var = if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" };
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gwm-hook"))
+        .args(args)
+        .env(home_var, home)
+        .current_dir(home)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn forwards_the_event_over_tcp() {
+    let home = TempDir::new("tcp");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    discover(&home, serde_json::json!({ "pid": 1, "port": port, "url": format!("http://127.0.0.1:{}/claude/status", port) }));
+    let server = serve_once(move || listener.accept().unwrap().0, "204 No Content");
+
+    let input = r#"{"session_id":"s","cwd":"/work/feature","hook_event_name":"PermissionRequest","tool_name":"Bash"}"#;
+    let output = run_hook(home.path(), &["--token", "secret", "PermissionRequest"], input);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty(), "hooks must not print decisions");
+
+    let received = server.join().unwrap();
+    assert!(received.head.starts_with("POST /claude/status "), "{}", received.head);
+    assert!(received.head.contains("Authorization: Bearer secret\r\n"), "{}", received.head);
+    assert!(received.head.contains("Host: localhost\r\n"), "{}", received.head);
+    assert_eq!(received.body, serde_json::json!({ "path": "/work/feature", "status": "waiting_auth", "message": null }));
+}
+
+#[cfg(unix)]
+#[test]
+fn prefers_the_unix_socket() {
+    let home = TempDir::new("socket");
+    let socket = home.path().join("hooks.sock");
+    let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+    // Nothing listens on the port: the hook must not need it
+    let unused = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    discover(&home, serde_json::json!({ "pid": 1, "port": unused, "url": "", "socket": socket }));
+    let server = serve_once(move || listener.accept().unwrap().0, "204 No Content");
+
+    let output = run_hook(home.path(), &["--token", "secret", "Stop"], r#"{"cwd":"/work/main","hook_event_name":"Stop"}"#);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(server.join().unwrap().body["status"], "idle");
+}
+
+#[test]
+fn reports_a_rejected_secret() {
+    let home = TempDir::new("rejected");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    discover(&home, serde_json::json!({ "pid": 1, "port": port, "url": "" }));
+    let server = serve_once(move || listener.accept().unwrap().0, "401 Unauthorized");
+
+    let output = run_hook(home.path(), &["--token", "stale", "PreToolUse"], r#"{"cwd":"/work"}"#);
+    server.join().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("401"));
+}
+
+#[test]
+fn stays_quiet_when_the_app_is_not_running() {
+    // No discovery file
+    let home = TempDir::new("absent");
+    let output = run_hook(home.path(), &["PostToolUse"], "{}");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // Discovery file left behind, nobody listening
+    let unused = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    discover(&home, serde_json::json!({ "pid": 1, "port": unused, "url": "" }));
+    let output = run_hook(home.path(), &["PostToolUse"], "{}");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stderr.is_empty());
+
+    // Events the hook is not installed for are ignored
+    let output = run_hook(home.path(), &["SessionStart"], "{}");
+    assert!(output.status.success());
+}
@@ -0,0 +1,150 @@
+// Removal safety of worktrees Smart Sync has linked dependencies into: the links
+// are not the user's work, so they must not make a worktree unsafe to remove.
+//
+//     cargo test --test removal_safety
+//
+// Needs `git` on the PATH to build the fixtures.
+
+mod common;
+
+use app_lib::{safety, smart_sync};
+use common::{git, path_str, try_git, write, TempDir};
+use std::path::PathBuf;
+
+// A main checkout with ignored dependencies and a `feature` worktree Smart Sync has linked them into.
+#[cfg(unix)]
+fn linked_worktree(dir: &TempDir) -> (PathBuf, PathBuf) {
+    let main = dir.path().join("main");
+    std::fs::create_dir_all(&main).unwrap();
+    git(&main, &["init", "-q", "-b", "main"]);
+    // The usual rule: a trailing slash, which git never matches against a symlink
+    write(&main.join(".gitignore"), "node_modules/\n");
+    git(&main, &["add", ".gitignore"]);
+    git(&main, &["commit", "-q", "-m", "Ignore dependencies"]);
+    write(&main.join("node_modules/pkg/index.js"), "module.exports = 1;\n");
+
+    let worktree = dir.path().join("feature");
+    git(&main, &["worktree", "add", "-q", "-b", "feature", &path_str(&worktree)]);
+    let sync = tauri::async_runtime::block_on(smart_sync::resync_worktree(path_str(&main), path_str(&worktree), None)).unwrap();
+    assert!(sync.items.iter().any(|i| i.used == Some(smart_sync::SyncStrategy::Symlink)), "{:?}", sync.items);
+    assert!(worktree.join("node_modules").symlink_metadata().unwrap().file_type().is_symlink());
+    (main, worktree)
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_dependencies_keep_a_worktree_safe() {
+    let dir = TempDir::new("symlink");
+    let (main, worktree) = linked_worktree(&dir);
+    // Excluded from git, so statuses and trash snapshots leave the link alone too
+    assert_eq!(git(&worktree, &["status", "--porcelain"]), "");
+
+    let report = safety::build_report(&path_str(&main), &path_str(&worktree), Some("feature"), Some("main")).unwrap();
+    assert!(report.is_safe, "uncommitted: {:?}", report.uncommitted_files);
+
+    // Synced by a version that did not exclude its links yet: the manifest still tells them apart
+    std::fs::remove_file(main.join(".git/info/exclude")).unwrap();
+    let report = safety::build_report(&path_str(&main), &path_str(&worktree), Some("feature"), Some("main")).unwrap();
+    assert!(report.is_safe, "uncommitted: {:?}", report.uncommitted_files);
+
+    // Real work next to the links is still caught
+    write(&worktree.join("notes.txt"), "todo\n");
+    let report = safety::build_report(&path_str(&main), &path_str(&worktree), Some("feature"), Some("main")).unwrap();
+    assert_eq!(report.uncommitted_files, vec!["?? notes.txt".to_string()]);
+}
+
+// A removal git refuses after the links came out leaves the worktree as it was.
+#[cfg(unix)]
+#[test]
+fn relink_restores_what_unlinking_took_out() {
+    let dir = TempDir::new("relink");
+    let (main, worktree) = linked_worktree(&dir);
+
+    let unlinked = smart_sync::unlink_before_removal(&path_str(&worktree)).unwrap();
+    assert_eq!(unlinked.len(), 1);
+    assert!(worktree.join("node_modules").symlink_metadata().is_err());
+    assert!(main.join("node_modules/pkg/index.js").exists());
+
+    smart_sync::relink(&path_str(&worktree), &unlinked).unwrap();
+    assert!(worktree.join("node_modules").symlink_metadata().unwrap().file_type().is_symlink());
+    assert!(worktree.join("node_modules/pkg/index.js").exists());
+    // Its exclude entry is back, so git still does not see the link
+    assert_eq!(git(&worktree, &["status", "--porcelain"]), "");
+}
+
+// Syncing from a worktree that is itself linked goes to the real folder, and a
+// worktree others do link into says so before it is removed.
+#[cfg(unix)]
+#[test]
+fn links_never_chain_through_worktrees() {
+    let dir = TempDir::new("chain");
+    let (main, linked) = linked_worktree(&dir);
+    let other = dir.path().join("other");
+    git(&main, &["worktree", "add", "-q", "-b", "other", &path_str(&other)]);
+    let sync = tauri::async_runtime::block_on(smart_sync::resync_worktree(path_str(&main), path_str(&other), Some(path_str(&linked)))).unwrap();
+    assert!(sync.items.iter().all(|i| i.error.is_none()), "{:?}", sync.items);
+    assert_eq!(std::fs::read_link(other.join("node_modules")).unwrap(), main.join("node_modules"));
+
+    // A worktree with dependencies of its own is a source others can point into
+    std::fs::remove_file(linked.join("node_modules")).unwrap();
+    write(&linked.join("node_modules/pkg/index.js"), "module.exports = 2;\n");
+    std::fs::remove_file(other.join("node_modules")).unwrap();
+    tauri::async_runtime::block_on(smart_sync::resync_worktree(path_str(&main), path_str(&other), Some(path_str(&linked)))).unwrap();
+    assert_eq!(std::fs::read_link(other.join("node_modules")).unwrap(), linked.join("node_modules"));
+
+    let report = safety::build_report(&path_str(&main), &path_str(&linked), Some("feature"), Some("main")).unwrap();
+    assert_eq!(report.linked_from, vec![path_str(&other)]);
+    assert!(report.is_safe);
+}
+
+// What removal and the rollback of a cancelled creation do to the worktree's
+// directory: unlink, let git remove it (forced for a rollback), then delete
+// whatever git left. None of it may reach the main checkout's files.
+#[cfg(unix)]
+#[test]
+fn removing_a_worktree_leaves_the_source_intact() {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = TempDir::new("intact");
+    let main = dir.path().join("main");
+    std::fs::create_dir_all(&main).unwrap();
+    git(&main, &["init", "-q", "-b", "main"]);
+    write(&main.join(".gitignore"), "node_modules/\n.env\n");
+    git(&main, &["add", ".gitignore"]);
+    git(&main, &["commit", "-q", "-m", "Ignore dependencies"]);
+    write(&main.join("node_modules/pkg/index.js"), "module.exports = 1;\n");
+    write(&main.join(".env"), "SECRET=1\n");
+    let config = smart_sync::SmartSyncConfig {
+        rules: vec![smart_sync::SyncRule { pattern: ".env".to_string(), strategy: smart_sync::SyncStrategy::Hardlink }],
+        ..Default::default()
+    };
+    tauri::async_runtime::block_on(smart_sync::set_smart_sync_config(path_str(&main), config)).unwrap();
+
+    for (name, force) in [("removed", false), ("rolled-back", true)] {
+        let worktree = dir.path().join(name);
+        git(&main, &["worktree", "add", "-q", "-b", name, &path_str(&worktree)]);
+        tauri::async_runtime::block_on(smart_sync::resync_worktree(path_str(&main), path_str(&worktree), None)).unwrap();
+        assert!(worktree.join("node_modules").symlink_metadata().unwrap().file_type().is_symlink());
+        assert_eq!(main.join(".env").metadata().unwrap().nlink(), 2);
+        // A link the manifest does not know about, e.g. made by hand
+        std::os::unix::fs::symlink(main.join("node_modules"), worktree.join("deps")).unwrap();
+
+        let unlinked = smart_sync::unlink_before_removal(&path_str(&worktree)).unwrap();
+        assert_eq!(unlinked.len(), 3, "{}: {:?}", name, unlinked);
+        let mut remove = vec!["worktree", "remove"];
+        if force {
+            remove.push("--force");
+        }
+        let worktree_str = path_str(&worktree);
+        remove.push(&worktree_str);
+        try_git(&main, &remove);
+        if worktree.exists() {
+            std::fs::remove_dir_all(&worktree).unwrap();
+        }
+
+        assert!(!worktree.exists(), "{}", name);
+        assert_eq!(std::fs::read_to_string(main.join("node_modules/pkg/index.js")).unwrap(), "module.exports = 1;\n", "{}", name);
+        assert_eq!(std::fs::read_to_string(main.join(".env")).unwrap(), "SECRET=1\n", "{}", name);
+        assert_eq!(main.join(".env").metadata().unwrap().nlink(), 1, "{}", name);
+    }
+}
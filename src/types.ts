@@ -2,4 +2,182 @@ export interface Worktree {
     path: string;
     head_hash: string;
     branch?: string;
+    is_bare: boolean;
+    is_detached: boolean;
+    is_locked: boolean;
+    lock_reason?: string;
+    is_prunable: boolean;
+    prunable_reason?: string;
 }
+
+export interface AheadBehind {
+    ahead: number;
+    behind: number;
+}
+
+export interface CommitInfo {
+    hash: string;
+    subject: string;
+    author: string;
+    timestamp: number;
+}
+
+export interface WorktreeStatus {
+    path: string;
+    staged: number;
+    unstaged: number;
+    untracked: number;
+    conflicted: number;
+    upstream?: string;
+    upstream_ahead_behind?: AheadBehind;
+    base?: string;
+    base_ahead_behind?: AheadBehind;
+    last_commit?: CommitInfo;
+    // Uncommitted changes to tracked files
+    diff?: DiffStats;
+    error?: string;
+}
+
+export interface DiffStats {
+    files_changed: number;
+    insertions: number;
+    deletions: number;
+}
+
+export interface Branch {
+    name: string;
+    is_remote: boolean;
+    target: string;
+    upstream?: string;
+}
+
+export interface RemovalReport {
+    worktree_path: string;
+    branch?: string;
+    uncommitted_files: string[];
+    unmerged_commits: { hash: string; subject: string }[];
+    stashes: { name: string; message: string }[];
+    // Worktrees whose Smart Sync links point into this one
+    linked_from: string[];
+    is_safe: boolean;
+}
+
+export interface TrashEntry {
+    id: string;
+    worktree_path: string;
+    branch?: string;
+    tip: string;
+    snapshot?: string;
+    trash_ref: string;
+    removed_at: number;
+}
+
+export type SyncStrategy = "symlink" | "hardlink" | "reflink" | "copy" | "skip";
+
+export interface SyncRule {
+    pattern: string;
+    strategy: SyncStrategy;
+}
+
+export type MismatchAction = "install" | "copy" | "link";
+
+export interface SmartSyncConfig {
+    file_mode: SyncStrategy;
+    rules: SyncRule[];
+    lockfile_mismatch: MismatchAction;
+    install_command?: string;
+}
+
+export interface SyncItemReport {
+    source: string;
+    destination: string;
+    is_dir: boolean;
+    strategy: SyncStrategy;
+    used?: SyncStrategy;
+    bytes: number;
+    error?: string;
+}
+
+export interface SyncReport {
+    source_path: string;
+    worktree_path: string;
+    created_at: number;
+    items: SyncItemReport[];
+    error?: string;
+    bootstrap?: BootstrapReport;
+}
+
+export type BootstrapStatus = "running" | "installed" | "copied" | "linked" | "skipped";
+
+export interface BootstrapReport {
+    lockfiles: string[];
+    action: MismatchAction;
+    command?: string;
+    status: BootstrapStatus;
+    exit_code?: number;
+    error?: string;
+}
+
+export interface BootstrapEvent {
+    worktree_path: string;
+    line?: string;
+    status: BootstrapStatus;
+}
+
+export interface PlannedItem {
+    source: string;
+    destination: string;
+    is_dir: boolean;
+    strategy: SyncStrategy;
+    exists: boolean;
+    estimated_bytes: number;
+}
+
+export interface SyncPlan {
+    items: PlannedItem[];
+    bootstrap?: BootstrapReport;
+}
+
+export interface SourceCandidate {
+    path: string;
+    branch?: string;
+    matching: string[];
+    differing: string[];
+    missing: string[];
+}
+
+export type ProgressPhase = "checkout" | "discover" | "sync" | "size" | "check" | "snapshot" | "unlink" | "remove" | "branch" | "roll_back";
+
+export interface ProgressEvent {
+    operation_id: string;
+    phase: ProgressPhase;
+    count: number;
+    total?: number;
+    current_path?: string;
+}
+
+// Error returned by every backend command
+export interface AppError {
+    kind: string;
+    message: string;
+    exit_code?: number;
+    stderr?: string;
+    remediation?: string;
+}
+
+// Pushed by the backend watcher when worktrees are added, removed or switch branches
+export interface WorktreeChangedEvent {
+    project_path: string;
+    worktrees: Worktree[];
+    // Fresh status of the worktrees the change touched
+    statuses: WorktreeStatus[];
+}
+
+// Local server the Claude hooks report to
+export type HookServerStatus =
+    | { state: "starting" }
+    // socket: Unix domain socket the hooks prefer over the port (Linux and macOS)
+    | { state: "running"; port: number; socket?: string }
+    | { state: "failed"; error: string }
+    // Another window of the app receives the hooks
+    | { state: "other_instance"; pid: number; port: number };